@@ -1,35 +1,566 @@
-use chrono::{DateTime, Datelike, Utc};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Utc, Weekday};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 use csv::Writer;
-use dialoguer::{Input, Select};
+use dialoguer::{Completion, Confirm, Input, MultiSelect, Select};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
+use std::fmt::Write as _;
 use std::fs::{self, File};
-use std::io::{self, ErrorKind, Write};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
 use std::result::Result;
 
+const CONFIG_FILE: &str = "config.json"; // Config of the default profile
+const DEFAULT_PROFILE: &str = "default"; // Profile used when --profile isn't given
+const CSV_FILE: &str = "expenses.csv";
+const CSV_TAG_SEPARATOR: &str = ";"; // Joins an expense's tags into the single CSV Tags field
+const MARKDOWN_FILE: &str = "expenses.md";
+const JSON_EXPORT_FILE: &str = "expenses_export.json"; // Default JSON export, kept apart from the data file
+const ENCRYPTED_MAGIC: &[u8] = b"EXPENC01"; // First bytes of an encrypted data file
+#[cfg(feature = "encryption")]
+const PASSPHRASE_ENV: &str = "EXPENSE_TRACKER_PASSPHRASE"; // Passphrase for encrypted data files, instead of a prompt
+const STDIO_PATH: &str = "-"; // `--file -` reads the data from stdin and writes it to stdout
+const SELF_NAME: &str = "Me"; // Participant name that stands for the user in split expenses
+const WIZARD_MONTHS: u32 = 6; // How many past months the budget wizard looks at
+const BASE_CURRENCY: &str = "USD"; // Default currency for expenses that don't name one
+const UNKNOWN_PAYMENT_METHOD: &str = "Unknown"; // Payment method when none was given
+const MAX_BACKUPS: usize = 5; // Backups of the data file kept before the oldest is deleted (one per session)
+const PAGE_SIZE: usize = 20; // Default number of expenses shown per page of the expense list
+const CHART_WIDTH: usize = 40; // Columns a 100% bar takes in the monthly summary chart
+const FUZZY_MATCH_THRESHOLD: f64 = 0.6; // Minimum normalized Levenshtein similarity for a fuzzy category match
+const DUPLICATE_WINDOW_MINUTES: i64 = 5; // How recent a same-category, same-amount entry must be to count as a likely duplicate
+const MIN_PLAUSIBLE_YEAR: i32 = 2000; // Entered dates before this year are rejected as typos
+const FUTURE_WARNING_DAYS: i64 = 1; // Entered dates further ahead than this need a confirmation
+
+// Result of an action that prompts the user; Err means a prompt was cancelled (e.g. Ctrl-C)
+// or the terminal went away, and the action stopped without finishing
+type PromptResult<T = ()> = Result<T, dialoguer::Error>;
+
+/*
+   Cli Struct:
+   - file (Option<String>): Path of the JSON data file. `-` switches to pipeline mode, where the
+     dataset is read from stdin and written back to stdout with no interactive prompts.
+     Taken from --file, else the EXPENSE_TRACKER_FILE environment variable, else the
+     profile's data file (see profile_paths).
+   - profile (String): Named set of data, budgets and config to use, e.g. "business".
+     Taken from --profile, else EXPENSE_TRACKER_PROFILE, else "default".
+   - command (Option<Command>): A one-shot action to run instead of the interactive menu
+     (e.g. from cron). Without one the menu starts as usual.
+*/
+#[derive(Parser, Debug)]
+#[command(version, about = "💰 A command-line expense tracker")]
+struct Cli {
+    /// JSON data file to load and save (`-` for stdin/stdout; default: the profile's file)
+    #[arg(long, global = true, env = "EXPENSE_TRACKER_FILE")]
+    file: Option<String>,
+    /// Profile to use; each has its own data file, budgets and config
+    #[arg(
+        long,
+        global = true,
+        env = "EXPENSE_TRACKER_PROFILE",
+        default_value = DEFAULT_PROFILE,
+        value_parser = parse_profile
+    )]
+    profile: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/*
+   Command Enum:
+   - Add: Records one expense (or income with --income) from flags and saves the data file.
+   - List: Prints every expense.
+   - Summary: Prints the current month's summary.
+   - Export: Writes the expenses as CSV or Markdown.
+*/
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Add an expense, e.g. `add --amount 12.50 --category Food`
+    Add {
+        #[arg(long, allow_negative_numbers = true)]
+        amount: f64,
+        #[arg(long)]
+        category: String,
+        #[arg(long, default_value = "")]
+        description: String,
+        /// Currency code (default: the configured default currency)
+        #[arg(long)]
+        currency: Option<String>,
+        /// Date in the configured input format (default: now)
+        #[arg(long, default_value = "")]
+        date: String,
+        #[arg(long, default_value = UNKNOWN_PAYMENT_METHOD)]
+        payment_method: String,
+        /// Comma-separated tags
+        #[arg(long, default_value = "")]
+        tags: String,
+        /// Record the entry as income instead of an expense
+        #[arg(long)]
+        income: bool,
+        /// Longer note, shown in the detail view
+        #[arg(long, default_value = "")]
+        note: String,
+    },
+    /// List all expenses
+    List,
+    /// Show this month's summary
+    Summary,
+    /// Export all expenses
+    Export {
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+        /// Output file (default: expenses.csv, expenses.md or expenses_export.json); `-` for stdout
+        #[arg(long)]
+        output: Option<String>,
+        /// Append per-category and grand total rows (CSV only)
+        #[arg(long)]
+        totals: bool,
+        /// Comma-separated CSV columns in the order to write them, e.g. "Date,Amount,Category"
+        /// (default: all of Id, Date, Category, Amount, Currency, Description, Tags, PaymentMethod,
+        /// Kind, Note)
+        #[arg(long)]
+        columns: Option<String>,
+    },
+}
+
 /*
 Expense Struct:
+- id (u64): Stable unique identifier, assigned once and never reused for another expense.
+  Legacy entries without one get a fresh ID when loaded (0 means "not assigned yet").
 - amount (f64): The expense value for arithmetic ops (e.g., total += expense.amount).
+- currency (String): ISO code the amount is in (e.g. "USD", "EUR"); older files default to USD.
+- kind (EntryKind): Whether the entry is money spent or money received; older files default to Expense.
 - category (String): Expense type for control-flow (e.g., if expense.category == "Food").
+- description (String): Optional free-text note to tell similar expenses apart; empty if none.
 - timestamp (DateTime<Utc>): When the expense occurred, for sorting/filtering by date.
+- split (Vec<SplitShare>): Who shares the expense and in which ratio; empty means it's all yours.
+- payment_method (String): How it was paid, e.g. "Cash" or "Visa"; older files default to "Unknown".
+- tags (Vec<String>): Tags such as "work" or "reimbursable", entered when adding the expense;
+  filter_by_tag and the CSV Tags column use them.
+- labels (Vec<String>): Labels attached later during review (e.g. "vacation"), see
+  review_labels and label_summary.
+  Description, split, tags and labels default to empty so older files without the fields
+  still load.
+- note (String): Longer free-text comment, only shown in the detail view; empty if none.
 */
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Expense {
+    #[serde(default)]
+    id: u64,
     amount: f64,
+    #[serde(default = "default_currency")]
+    currency: String,
+    #[serde(default)]
+    kind: EntryKind,
     category: String,
+    #[serde(default)]
+    description: String,
     timestamp: DateTime<Utc>,
+    #[serde(default)]
+    split: Vec<SplitShare>,
+    #[serde(default = "default_payment_method")]
+    payment_method: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    note: String,
+}
+
+/*
+EntryKind Enum:
+- Expense: Money spent; counted in spending totals and budget checks.
+- Income: Money received; only counted towards income and net cash flow.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum EntryKind {
+    #[default]
+    Expense,
+    Income,
+}
+
+impl Expense {
+    fn is_income(&self) -> bool {
+        self.kind == EntryKind::Income
+    }
+}
+
+// Serde default for Expense::currency, so entries saved before currencies existed load as USD
+fn default_currency() -> String {
+    BASE_CURRENCY.to_string()
+}
+
+// Serde default for Expense::payment_method, for entries saved before it was recorded
+fn default_payment_method() -> String {
+    UNKNOWN_PAYMENT_METHOD.to_string()
+}
+
+/*
+SplitShare Struct:
+- person (String): Participant responsible for this part (SELF_NAME for the user).
+- ratio (f64): Fraction of the expense amount this person covers; all ratios add up to 1.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct SplitShare {
+    person: String,
+    ratio: f64,
+}
+
+/*
+RecurringTemplate Struct:
+- category (String) / amount (f64) / currency (String): What gets posted each month.
+- day (u32): Day of the month the expense is due (1–31; clamped to shorter months).
+- last_posted (Option<String>): "YYYY-MM" of the last month an occurrence was posted,
+  so launching twice in one month doesn't post it again.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RecurringTemplate {
+    category: String,
+    amount: f64,
+    #[serde(default = "default_currency")]
+    currency: String,
+    day: u32,
+    #[serde(default)]
+    last_posted: Option<String>,
+}
+
+/*
+   ReportFormat Enum:
+   - Csv: Month-end reports are written as `Category,Currency,Total` rows.
+   - Markdown: Month-end reports are written as a Markdown table.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ReportFormat {
+    Csv,
+    Markdown,
+}
+
+/*
+   ExportFormat Enum:
+   - The formats the `export` subcommand can write the expenses in: Csv, Markdown or Json.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Markdown,
+    Json,
+}
+
+/*
+   CsvColumn Enum:
+   - One variant per column export_to_csv can write. ALL is the full set in the default
+     order; new columns go at the end so existing readers keep working.
+   - header gives a column's name and value its field of an expense, so the header row and
+     the expense rows come from the same list of columns and can't get out of step.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CsvColumn {
+    Id,
+    Date,
+    Category,
+    Amount,
+    Currency,
+    Description,
+    Tags,
+    PaymentMethod,
+    Kind,
+    Note,
+}
+
+impl CsvColumn {
+    const ALL: [CsvColumn; 10] = [
+        CsvColumn::Id,
+        CsvColumn::Date,
+        CsvColumn::Category,
+        CsvColumn::Amount,
+        CsvColumn::Currency,
+        CsvColumn::Description,
+        CsvColumn::Tags,
+        CsvColumn::PaymentMethod,
+        CsvColumn::Kind,
+        CsvColumn::Note,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            CsvColumn::Id => "Id",
+            CsvColumn::Date => "Date",
+            CsvColumn::Category => "Category",
+            CsvColumn::Amount => "Amount",
+            CsvColumn::Currency => "Currency",
+            CsvColumn::Description => "Description",
+            CsvColumn::Tags => "Tags",
+            CsvColumn::PaymentMethod => "PaymentMethod",
+            CsvColumn::Kind => "Kind",
+            CsvColumn::Note => "Note",
+        }
+    }
+
+    /*
+       The column's field of `expense`, `row` being its 1-based row in the export:
+       - Id: The expense's stable ID. An expense without one (ID 0) falls back to `row`.
+       - Date: The UTC timestamp in RFC 3339 (e.g. "2024-05-01T09:30:00+00:00"), which
+               parse_csv_timestamp reads back exactly.
+       - Amount: The amount (f64) converted to a string.
+       - Description: Written as-is (an empty field when there is none).
+       - Tags: The tags joined with CSV_TAG_SEPARATOR (e.g. "work;travel"), empty if none.
+       - PaymentMethod: How it was paid ("Unknown" if not recorded).
+       - Kind: "expense" or "income", as in the data file.
+       - Note: The free-form note, empty if none.
+    */
+    fn value(self, expense: &Expense, row: usize) -> String {
+        match self {
+            CsvColumn::Id => match expense.id {
+                0 => row.to_string(),
+                id => id.to_string(),
+            },
+            CsvColumn::Date => expense.timestamp.to_rfc3339(),
+            CsvColumn::Category => expense.category.clone(),
+            CsvColumn::Amount => expense.amount.to_string(),
+            CsvColumn::Currency => expense.currency.clone(),
+            CsvColumn::Description => expense.description.clone(),
+            CsvColumn::Tags => expense.tags.join(CSV_TAG_SEPARATOR),
+            CsvColumn::PaymentMethod => expense.payment_method.clone(),
+            CsvColumn::Kind => match expense.kind {
+                EntryKind::Expense => "expense".to_string(),
+                EntryKind::Income => "income".to_string(),
+            },
+            CsvColumn::Note => expense.note.clone(),
+        }
+    }
+
+    // Finds a column by its header, ignoring case and spaces ("payment method" works too)
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.replace(' ', "");
+        Self::ALL
+            .into_iter()
+            .find(|column| column.header().eq_ignore_ascii_case(&name))
+    }
+}
+
+/*
+   SortOrder Enum:
+   - The orderings offered by the "Sort Expenses" menu.
+   - Also used by config.default_sort to order the list right after loading.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum SortOrder {
+    AmountAsc,
+    AmountDesc,
+    Category,
+    DateNewest,
+    DateOldest,
+}
+
+/*
+   StreakRule Enum:
+   - Logged: A day counts towards the streak if at least one expense was logged on it.
+   - UnderBudget: A day counts if its spending stayed within config.daily_budget.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum StreakRule {
+    Logged,
+    UnderBudget,
+}
+
+/*
+   Rounding Enum:
+   - How amounts are rounded to whole cents before any total is added up (summaries, budget
+     checks, list footers, exports), so a total always matches the sum of the amounts as printed.
+   - HalfUp: ties go away from zero, like most receipts (0.005 -> 0.01, 0.015 -> 0.02).
+   - HalfEven: banker's rounding, ties go to the even cent (0.005 -> 0.00, 0.015 -> 0.02).
+   round_cents snaps off floating-point noise first, so 1.005 (stored as 1.00499999...)
+   still counts as a tie.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Rounding {
+    HalfUp,
+    HalfEven,
+}
+
+impl Rounding {
+    fn round_cents(self, amount: f64) -> f64 {
+        let cents = (amount * 100.0 * 1e6).round() / 1e6;
+        let cents = match self {
+            Rounding::HalfUp => cents.round(),
+            Rounding::HalfEven => cents.round_ties_even(),
+        };
+        cents / 100.0
+    }
+}
+
+/*
+   Config Struct:
+   - reports_dir (String): Folder the month-end reports are saved into.
+   - report_format (ReportFormat): Output format for the month-end reports.
+   - last_report_month (Option<String>): "YYYY-MM" of the last month a report was offered for,
+     so the prompt only shows up once per month.
+   - default_sort (Option<SortOrder>): Order applied to the expenses after loading, if any.
+   - streak_rule (StreakRule): Which days count towards the spending streak shown on launch.
+   - daily_budget (Option<f64>): Daily spending limit used by the UnderBudget streak rule.
+   - date_input_format (String): chrono format used to read dates typed by the user.
+   - date_display_format (String): chrono format used to show dates in lists and reports
+     (see format_local), e.g. "%b %-d, %Y %H:%M" for "Jan 3, 2024 14:05".
+   - percent_decimals (usize): Decimal places shown for every printed percentage.
+   - rates_file (String): JSON file the static exchange-rate provider reads from.
+   - soft_delete (bool): When true, deleted expenses go to the trash instead of being removed.
+   - default_currency (String): Currency offered when adding an expense or recurring bill,
+     and used for imported rows without one.
+   - warn_threshold (f64): Fraction of a limit budget (0.8 = 80%) from which adding an
+     expense gives a "nearing the budget" warning.
+   - page_size (usize): Expenses shown per page in "View Expenses".
+   - encrypt_data (bool): When true, the data file and the files next to it are saved
+     encrypted with a passphrase (see EncryptedStorage). Files that are already encrypted stay encrypted either way.
+   - autosave (bool): When true, the menu saves everything right after an add, edit,
+     delete or budget change (see autosave).
+   - rounding (Rounding): How amounts are rounded to cents before summaries add them up.
+   - inflation_index (BTreeMap<i32, f64>): A price index per year (e.g. the CPI, 2015 = 100)
+     used to restate spending in another year's prices (see inflation_report).
+   Missing fields fall back to their defaults thanks to #[serde(default)].
+*/
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+struct Config {
+    reports_dir: String,
+    report_format: ReportFormat,
+    last_report_month: Option<String>,
+    default_sort: Option<SortOrder>,
+    streak_rule: StreakRule,
+    daily_budget: Option<f64>,
+    date_input_format: String,
+    date_display_format: String,
+    percent_decimals: usize,
+    rates_file: String,
+    soft_delete: bool,
+    default_currency: String,
+    warn_threshold: f64,
+    page_size: usize,
+    encrypt_data: bool,
+    autosave: bool,
+    rounding: Rounding,
+    inflation_index: BTreeMap<i32, f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            reports_dir: "reports".to_string(),
+            report_format: ReportFormat::Markdown,
+            last_report_month: None,
+            default_sort: None,
+            streak_rule: StreakRule::Logged,
+            daily_budget: None,
+            date_input_format: "%Y-%m-%d".to_string(),
+            date_display_format: "%b %-d, %Y %H:%M".to_string(),
+            percent_decimals: 1,
+            rates_file: "rates.json".to_string(),
+            soft_delete: false,
+            default_currency: BASE_CURRENCY.to_string(),
+            warn_threshold: 0.8,
+            page_size: PAGE_SIZE,
+            encrypt_data: false,
+            autosave: true,
+            rounding: Rounding::HalfUp,
+            inflation_index: BTreeMap::new(),
+        }
+    }
+}
+
+/*
+   BudgetKind Enum:
+   - Limit: A maximum to spend; warns when spending goes above it.
+   - Savings: A minimum to set aside; warns while contributions are still below it.
+   Budgets are stored as plain numbers, a negative value marks a savings target
+   (so -200.0 means "save at least $200"). BudgetKind::of splits the two apart.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BudgetKind {
+    Limit,
+    Savings,
+}
+
+impl BudgetKind {
+    fn of(budget: f64) -> (Self, f64) {
+        if budget < 0.0 {
+            (BudgetKind::Savings, -budget)
+        } else {
+            (BudgetKind::Limit, budget)
+        }
+    }
+}
+
+/*
+   BudgetStatus Enum:
+   - Under: Spending is below the warning threshold of a limit budget.
+   - Near: Spending has reached the threshold (e.g. 80%) but not gone over the limit.
+   - Over: Spending is above the limit.
+   Worked out by budget_status.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BudgetStatus {
+    Under,
+    Near,
+    Over,
+}
+
+/*
+   UndoPoint Struct:
+   - action (&'static str): What is being undone, e.g. "delete", for the confirmation message.
+   - expenses / trash (Vec<Expense>): Copies of both lists from just before the action.
+*/
+struct UndoPoint {
+    action: &'static str,
+    expenses: Vec<Expense>,
+    trash: Vec<Expense>,
 }
 
 /*
    ExpenseTracker Struct:
    - expenses (Vec<Expense>): A collection of expense entries for arithmetic operations (e.g., summing totals).
    - budgets (HashMap<String, f64>): Budget limits by category, used in control-flow for budget checks.
+     Negative values are savings targets (see BudgetKind).
+   - config (Config): User settings loaded from config.json.
+   - trash (Vec<Expense>): Soft-deleted expenses that can still be restored.
+   - next_id (u64): Monotonic counter handing out expense IDs.
+   - rates (StaticRateProvider): Exchange rates set by the user against BASE_CURRENCY, in
+     the same representation as the rates file, used to convert the monthly summary.
+   - recurring (Vec<RecurringTemplate>): Monthly bills posted automatically on startup.
+   - undo (Option<UndoPoint>): State before the last add, edit or delete (one level of undo).
+     Saving doesn't touch it, so an action can still be undone after a save.
+   - storage (Box<dyn Storage>): Where the expenses are loaded from and saved to.
+   - config_path (String): The profile's config file, where settings changes are saved.
+   - monthly_budget (Option<f64>): Overall spending limit for a month across all categories,
+     checked on top of the per-category budgets.
+   - unsaved_changes (bool): Set by every change made through the methods below and by budget
+     changes, cleared once everything has been saved; tells autosave when to write.
+   - last_category (Option<String>): Category of the last entry added this session, offered
+     again by add_expense. Not saved.
 */
 struct ExpenseTracker {
     expenses: Vec<Expense>,
     budgets: HashMap<String, f64>, // Stores budget limits per category
+    config: Config,
+    trash: Vec<Expense>,
+    next_id: u64,
+    rates: StaticRateProvider,
+    recurring: Vec<RecurringTemplate>,
+    undo: Option<UndoPoint>,
+    storage: Box<dyn Storage>,
+    config_path: String,
+    monthly_budget: Option<f64>,
+    unsaved_changes: bool,
+    last_category: Option<String>,
 }
 
 /*
@@ -38,440 +569,8932 @@ struct ExpenseTracker {
    - Initializes:
        • expenses with Vec::new() for collecting expense entries.
        • budgets with HashMap::new() for storing category budget limits.
+       • config with the default settings.
+       • trash with Vec::new(), as nothing has been soft-deleted yet.
+       • next_id with 1, so the first expense gets ID 1.
+       • rates with StaticRateProvider::new(BASE_CURRENCY), no exchange rates set yet.
+       • recurring with Vec::new(), no recurring expenses yet.
+       • undo with None, nothing to undo yet.
+       • storage with an empty MemoryStorage, so a fresh tracker never touches a file;
+         load_tracker swaps in the data file's backend.
+       • config_path with CONFIG_FILE, the default profile's config.
+       • monthly_budget with None, no overall budget yet.
+       • unsaved_changes with false, as nothing has changed yet.
+       • last_category with None, nothing added this session yet.
+   - allocate_id() -> u64: Hands out the next unused ID and advances the counter.
+   - snapshot(action) -> UndoPoint: Copies the expenses and trash before `action` changes them;
+     callers store it in `undo` once the action has gone through.
+   - undo_last() -> Option<&str>: Restores the last snapshot and returns its action name.
+   - spent_in(category, year, month) -> f64: Total spending (income left out) in a category
+     during the given month, shared by the budget checks since budgets reset every month.
+   - spent_overall(year, month) -> f64: Same across all categories, for the overall monthly budget.
+   The methods below change the data without any prompts or output; the interactive
+   functions collect the input, call them and report the result:
+   - add(expense) -> u64: Gives the expense the next ID, appends it and returns the ID.
+   - add_all(action, expenses) -> usize: Same for a batch (imports, recurring bills, copies)
+     under one undo point named `action`; returns how many were added. An empty batch
+     changes nothing.
+   - delete_indices(&[usize]) -> Result<Vec<Expense>, String>: Removes the expenses at the
+     given 0-based positions (repeats count once) and returns them. Fails without changing
+     anything if a position is out of range. With soft delete on, copies go to the trash.
+   - delete_by_index(usize) / delete_by_id(u64) -> Result<Expense, String>: Same for one expense.
+   - edit(index, ExpenseEdit) -> Result<(), String>: Changes one field; an invalid amount or
+     position is refused.
+   - set_budget(category, kind, amount): Stores a limit or savings target (see BudgetKind);
+     an amount that isn't a finite number is refused and nothing changes.
+   - remove_budget(category) -> Option<f64>: Drops a category's budget, returning it if there was one.
+   - merge_category(source, target) -> usize: Moves every expense whose category matches
+     `source` (ignoring case) to `target` and returns how many moved. A budget on the source
+     carries over to the target unless the target already has one; either way the source's
+     budget is dropped. Undo brings the expenses back, not the budget.
+   - rename_category(old, new) -> usize: The same move, undone as a "rename"; renaming onto a
+     category that is already in use merges the two.
+   Each successful change keeps the previous state in `undo`.
 */
 impl ExpenseTracker {
     fn new() -> Self {
         Self {
             expenses: Vec::new(),
             budgets: HashMap::new(),
+            config: Config::default(),
+            trash: Vec::new(),
+            next_id: 1,
+            rates: StaticRateProvider::new(BASE_CURRENCY),
+            recurring: Vec::new(),
+            undo: None,
+            storage: Box::new(MemoryStorage::default()),
+            config_path: CONFIG_FILE.to_string(),
+            monthly_budget: None,
+            unsaved_changes: false,
+            last_category: None,
         }
     }
-}
-
-fn main() {
-    println!("💰 Welcome to the Rust Expense Tracker!");
-
-    let mut tracker = ExpenseTracker::new();
-    tracker.expenses = load_expenses();
-
-    /*
-       Main Loop:
-       - Defines menu choices (with emojis) for various expense tracker actions.
-       - Uses an interactive prompt (via Select) to capture the user's selection.
-       - Executes the corresponding function based on the choice
-    */
-    loop {
-        let choices = vec![
-            "➕ Add Expense",
-            "📋 View Expenses",
-            "📊 Sort Expenses",
-            "📊 Filter Expenses",
-            "📅 Monthly Summary",
-            "⚠️ Set Budget Limit",
-            "🗑️ Delete an Expense",
-            "📁 Export to CSV",
-            "💾 Save & Exit",
-        ];
 
-        let selection = Select::new()
-            .with_prompt("📌 Choose an option")
-            .default(0)
-            .items(&choices)
-            .interact()
-            .unwrap();
+    fn allocate_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
 
-        /*
-           This match block controls the program's flow based on the user's menu selection:
-           - 0: Call add_expense, passing a mutable reference to the tracker.
-           - 1: Call view_expenses, displaying the list of expenses.
-           - 2: Call sort_expenses to order the expenses.
-           - 3: Call filter_expenses to show a subset of expenses.
-           - 4: Call monthly_summary to generate a report.
-           - 5: Call set_budget to adjust budget limits.
-           - 6: Call delete_expenses to remove an expense.
-           - 7: Attempt to export expenses to CSV; if it fails, print an error message.
-           - 8: Save expenses, print a goodbye message, and break out of the loop to exit.
-           - _: Handle any invalid selection with a warning message.
-        */
-        match selection {
-            0 => add_expense(&mut tracker),
-            1 => view_expenses(&mut tracker.expenses),
-            2 => sort_expenses(&mut tracker.expenses),
-            3 => filter_expenses(&tracker.expenses),
-            4 => monthly_summary(&tracker.expenses),
-            5 => set_budget(&mut tracker),
-            6 => delete_expenses(&mut tracker.expenses),
-            7 => {
-                if let Err(e) = export_to_csv(&tracker.expenses) {
-                    println!("⚠️ Failed to export: {}", e);
-                }
-            }
-            8 => {
-                save_expenses(&tracker.expenses);
-                println!("👋 Exiting program... Goodbye!");
-                break;
-            }
-            _ => println!("⚠️ Invalid choice! Please try again."),
+    fn snapshot(&self, action: &'static str) -> UndoPoint {
+        UndoPoint {
+            action,
+            expenses: self.expenses.clone(),
+            trash: self.trash.clone(),
         }
     }
-}
 
-// Function to add an expense
-fn add_expense(tracker: &mut ExpenseTracker) {
-    let category: String = Input::new()
-        .with_prompt("Enter expense category:")
-        .interact_text()
-        .unwrap();
+    fn undo_last(&mut self) -> Option<&'static str> {
+        let point = self.undo.take()?;
+        self.expenses = point.expenses;
+        self.trash = point.trash;
+        self.unsaved_changes = true;
+        Some(point.action)
+    }
 
-    let amount: f64 = Input::new()
-        .with_prompt("Enter expense amount:")
-        .interact_text()
-        .unwrap();
+    fn spent_in(&self, category: &str, year: i32, month: u32) -> f64 {
+        self.expenses
+            .iter()
+            .filter(|e| !e.is_income() && e.category == category && in_month(e, year, month))
+            .map(|e| self.config.rounding.round_cents(e.amount))
+            .sum()
+    }
 
-    /*
-       Adds a new expense entry to the tracker's expenses vector:
-       - category: Clones the category string to ensure ownership.
-       - amount: Uses the provided expense value (f64) for calculations.
-       - timestamp: Records the current UTC time using chrono::Utc::now().
-    */
-    tracker.expenses.push(Expense {
-        category: category.clone(),
-        amount,
-        timestamp: chrono::Utc::now(),
-    });
+    fn spent_overall(&self, year: i32, month: u32) -> f64 {
+        self.expenses
+            .iter()
+            .filter(|e| !e.is_income() && in_month(e, year, month))
+            .map(|e| self.config.rounding.round_cents(e.amount))
+            .sum()
+    }
 
-    println!("✅ Expense added: {} - ${:.2}", category, amount);
+    fn add(&mut self, mut expense: Expense) -> u64 {
+        self.undo = Some(self.snapshot("add"));
+        expense.id = self.allocate_id();
+        let id = expense.id;
+        self.expenses.push(expense);
+        self.unsaved_changes = true;
+        id
+    }
 
-    /*
-       Checks if a budget exists for the given category and warns if spending exceeds it.
+    fn add_all(&mut self, action: &'static str, expenses: Vec<Expense>) -> usize {
+        if expenses.is_empty() {
+            return 0;
+        }
+        self.undo = Some(self.snapshot(action));
+        let count = expenses.len();
+        for mut expense in expenses {
+            expense.id = self.allocate_id();
+            self.expenses.push(expense);
+        }
+        self.unsaved_changes = true;
+        count
+    }
 
-       - `if let Some(&budget) = tracker.budgets.get(&category)`:
-           Attempts to retrieve the budget for the category.
-           If found, destructures the value (using & to dereference) into `budget`.
+    fn delete_indices(&mut self, indices: &[usize]) -> Result<Vec<Expense>, String> {
+        let mut indices = indices.to_vec();
+        // Highest first, so each removal leaves the positions still to go in place.
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices.dedup();
+        match indices.first() {
+            None => return Err("No expense selected.".to_string()),
+            Some(&highest) if highest >= self.expenses.len() => {
+                return Err(format!("There is no expense #{}.", highest + 1));
+            }
+            Some(_) => {}
+        }
 
-       - Calculates total spending for the category:
-           • Iterates over `tracker.expenses`.
-           • Filters expenses that match the category.
-           • Maps each expense to its amount.
-           • Sums all amounts to get `total_spent` (arithmetic sum of f64 values).
+        self.undo = Some(self.snapshot("delete"));
+        let removed: Vec<Expense> = indices
+            .into_iter()
+            .map(|index| self.expenses.remove(index))
+            .collect();
+        if self.config.soft_delete {
+            self.trash.extend(removed.iter().cloned());
+        }
+        self.unsaved_changes = true;
+        Ok(removed)
+    }
 
-       - Compares `total_spent` with the budget:
-           If spending exceeds the budget, prints a warning message.
-    */
-    if let Some(&budget) = tracker.budgets.get(&category) {
-        let total_spent: f64 = tracker
+    fn delete_by_index(&mut self, index: usize) -> Result<Expense, String> {
+        let mut removed = self.delete_indices(&[index])?;
+        Ok(removed.remove(0))
+    }
+
+    fn delete_by_id(&mut self, id: u64) -> Result<Expense, String> {
+        let index = self
             .expenses
             .iter()
-            .filter(|e| e.category == category)
-            .map(|e| e.amount)
-            .sum();
+            .position(|e| e.id == id)
+            .ok_or_else(|| format!("No expense with ID {}.", id))?;
+        self.delete_by_index(index)
+    }
 
-        if total_spent > budget {
-            println!(
-                "⚠️ Warning: You have exceeded your budget of ${:.2} for '{}'.",
-                budget, category
-            );
+    fn edit(&mut self, index: usize, edit: ExpenseEdit) -> Result<(), String> {
+        if index >= self.expenses.len() {
+            return Err(format!("There is no expense #{}.", index + 1));
+        }
+        if let ExpenseEdit::Amount(amount) = edit {
+            validate_amount(amount)?;
         }
+
+        self.undo = Some(self.snapshot("edit"));
+        let expense = &mut self.expenses[index];
+        match edit {
+            ExpenseEdit::Amount(amount) => expense.amount = amount,
+            ExpenseEdit::Category(category) => expense.category = normalize_category(&category),
+            ExpenseEdit::Description(description) => {
+                expense.description = description.trim().to_string()
+            }
+            ExpenseEdit::Date(timestamp) => expense.timestamp = timestamp,
+            ExpenseEdit::Note(note) => expense.note = note.trim().to_string(),
+            ExpenseEdit::Split(split) => expense.split = split,
+        }
+        self.unsaved_changes = true;
+        Ok(())
     }
-}
 
-/*
-   view_expenses Function:
-   - Displays the list of recorded expenses in a formatted manner.
-   - Steps:
-       1. Prints a header ("Expense List") with bold and underline formatting.
-       2. Checks if there are any expenses:
-            • If empty, prints a warning and exits the function.
-       3. Otherwise, prints a sub-header ("Your Expenses") and a divider.
-       4. Iterates through expenses with enumeration:
-            • Formats and prints each expense with its index, category, timestamp, and amount.
-       5. Ends by printing a closing divider.
-*/
-fn view_expenses(expenses: &Vec<Expense>) {
-    println!("\n{}", "📋 Expense List".bold().underline());
+    fn set_budget(&mut self, category: &str, kind: BudgetKind, amount: f64) -> Result<(), String> {
+        if !amount.is_finite() {
+            return Err("Budget must be a valid number.".to_string());
+        }
+        let amount = amount.abs();
+        let stored = match kind {
+            BudgetKind::Limit => amount,
+            BudgetKind::Savings => -amount,
+        };
+        self.budgets.insert(category.to_string(), stored);
+        self.unsaved_changes = true;
+        Ok(())
+    }
 
-    if expenses.is_empty() {
-        println!("{}", "⚠️ No expenses recorded yet.".yellow());
-        return;
+    fn remove_budget(&mut self, category: &str) -> Option<f64> {
+        let removed = self.budgets.remove(category);
+        self.unsaved_changes |= removed.is_some();
+        removed
     }
 
-    println!("\n💰 Your Expenses:");
-    println!("-------------------------");
+    // Moves the trashed expense at `position` back to the end of the list (undoable)
+    fn restore_from_trash(&mut self, position: usize) {
+        self.undo = Some(self.snapshot("restore"));
+        let restored = self.trash.remove(position);
+        self.expenses.push(restored);
+        self.unsaved_changes = true;
+    }
 
-    for (i, expense) in expenses.iter().enumerate() {
-        println!(
-            "{} {} - {} - ${:.2}",
-            format!("#{}", i + 1).cyan(),
-            expense.category.green(),
-            expense.timestamp.to_string().purple(),
-            expense.amount
-        );
+    fn empty_trash(&mut self) {
+        self.trash.clear();
+        self.unsaved_changes = true;
     }
 
-    println!("-------------------------");
-}
+    fn merge_category(&mut self, source: &str, target: &str) -> usize {
+        self.recategorize("merge", source, target)
+    }
 
-// Function to sort expenses
-fn sort_expenses(expenses: &mut Vec<Expense>) {
-    println!("\n📌 Choose sorting option:");
-    println!("1️⃣ By Amount (Low to High)");
-    println!("2️⃣ By Amount (High to Low)");
-    println!("3️⃣ By Category (A-Z)");
-    println!("4️⃣ By Date (Newest First)");
-    println!("5️⃣ By Date (Oldest First)");
+    fn rename_category(&mut self, old: &str, new: &str) -> usize {
+        self.recategorize("rename", old, new)
+    }
 
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read user input");
-    let input = input.trim();
+    fn recategorize(&mut self, action: &'static str, source: &str, target: &str) -> usize {
+        let matches = |category: &str| category.eq_ignore_ascii_case(source) && category != target;
+        if !self.expenses.iter().any(|e| matches(&e.category)) {
+            return 0;
+        }
 
-    /*
-       Match on the user input to sort the expenses vector accordingly:
-
-       - "1": Sort expenses in ascending order by amount.
-              Uses partial_cmp to compare f64 values (unwrap assumes no NaN).
-       - "2": Sort expenses in descending order by amount.
-              Reverses the order by swapping a and b.
-       - "3": Sort expenses alphabetically by category.
-              Uses cmp for String comparison.
-       - "4": Sort expenses in descending order by timestamp.
-              The most recent expenses come first.
-       - "5": Sort expenses in ascending order by timestamp.
-              The oldest expenses come first.
-       - _ (any other input): Print an error message and return from the function.
-    */
-    match input {
-        "1" => expenses.sort_by(|a, b| a.amount.partial_cmp(&b.amount).unwrap()),
-        "2" => expenses.sort_by(|a, b| b.amount.partial_cmp(&a.amount).unwrap()),
-        "3" => expenses.sort_by(|a, b| a.category.cmp(&b.category)),
-        "4" => expenses.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)),
-        "5" => expenses.sort_by(|a, b| a.timestamp.cmp(&b.timestamp)),
-        _ => {
-            println!(" ⚠️ Invalid choice! Returning to menu");
-            return;
+        self.undo = Some(self.snapshot(action));
+        let mut moved = 0;
+        for expense in self.expenses.iter_mut().filter(|e| matches(&e.category)) {
+            expense.category = target.to_string();
+            moved += 1;
         }
+
+        let source_keys: Vec<String> = self
+            .budgets
+            .keys()
+            .filter(|k| matches(k))
+            .cloned()
+            .collect();
+        for key in source_keys {
+            if let Some(budget) = self.budgets.remove(&key) {
+                self.budgets.entry(target.to_string()).or_insert(budget);
+            }
+        }
+        self.unsaved_changes = true;
+        moved
     }
+}
 
-    println!("\n✅ Expenses sorted!");
-    view_expenses(expenses);
+/*
+   ExpenseEdit Enum:
+   - One change to an existing expense, applied by ExpenseTracker::edit.
+   - Amount(f64), Category(String), Description(String) (blank clears it), Date(DateTime<Utc>),
+     Note(String) (blank clears it), Split(Vec<SplitShare>) (empty removes the split).
+*/
+#[derive(Debug, Clone, PartialEq)]
+enum ExpenseEdit {
+    Amount(f64),
+    Category(String),
+    Description(String),
+    Date(DateTime<Utc>),
+    Note(String),
+    Split(Vec<SplitShare>),
 }
 
 /*
-   filter_expenses function:
-   - Prompts the user to enter a category for filtering.
-   - Reads and trims user input from stdin.
-   - Filters the expenses vector, selecting only those expenses whose category
-     matches the input, ignoring case differences.
-   - If no matching expenses are found, prints a warning.
-   - Otherwise, prints the amounts for all matching expenses.
+   assign_missing_ids Function:
+   - Walks the expenses and the trash together, since they share one ID space.
+   - Keeps every ID that is set and not already taken.
+   - Gives legacy entries (ID 0) and duplicates a fresh ID above the highest one in use.
+   - Returns the next free ID for the tracker's counter.
 */
-fn filter_expenses(expenses: &Vec<Expense>) {
-    println!("\n📌 Enter category to filter:");
+fn assign_missing_ids(expenses: &mut [Expense], trash: &mut [Expense]) -> u64 {
+    let mut next_id = expenses
+        .iter()
+        .chain(trash.iter())
+        .map(|e| e.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut seen: HashSet<u64> = HashSet::new();
 
-    let mut category = String::new();
-    io::stdin()
-        .read_line(&mut category)
-        .expect("Failed to read user input");
-    let category = category.trim();
+    for expense in expenses.iter_mut().chain(trash.iter_mut()) {
+        if expense.id == 0 || !seen.insert(expense.id) {
+            expense.id = next_id;
+            seen.insert(next_id);
+            next_id += 1;
+        }
+    }
 
-    let filtered: Vec<&Expense> = expenses
-        .iter()
-        .filter(|expense| expense.category.eq_ignore_ascii_case(category))
-        .collect();
+    next_id
+}
 
-    if filtered.is_empty() {
-        println!("\n ⚠️ No expenses found for category: {}", category);
-    } else {
-        println!("\n📌 Expenses in category '{}':", category);
-        println!("-------------------------");
+// Path of a file stored next to the data file, e.g. ("expenses.json", "trash") -> "expenses.trash.json"
+fn companion_path(data_path: &str, name: &str) -> PathBuf {
+    Path::new(data_path).with_extension(format!("{}.json", name))
+}
 
-        for expense in filtered {
-            println!("Amount: ${:.2}", expense.amount);
+/*
+   read_companion Function:
+   - Reads one of the JSON files kept next to the data file (budgets, trash, ...), passing
+     the contents through storage.open so an encrypted one is decrypted first.
+   - A missing file gives None: nothing of that kind has been saved yet.
+   - A file that doesn't parse is moved aside with quarantine_file, the same way
+     load_json_expenses treats the data file, and also gives None; the next save then
+     writes a fresh file instead of overwriting the broken one. `what` names it in messages.
+   - Any other read error, a file that can't be decrypted, or a broken file that can't be
+     moved is returned, so the caller stops rather than let the next save wipe the file.
+*/
+fn read_companion<T: DeserializeOwned>(
+    storage: &dyn Storage,
+    path: &Path,
+    what: &str,
+) -> Result<Option<T>, Box<dyn Error>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(format!("could not read {}: {}", path.display(), error).into()),
+    };
+    let data = storage
+        .open(&data)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    match serde_json::from_slice(&data) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            println!(
+                "{}",
+                format!("⚠️ Could not parse {}: {}", path.display(), e).red()
+            );
+            let moved = quarantine_file(path).map_err(|e| {
+                format!(
+                    "{} is unreadable and could not be moved aside ({})",
+                    path.display(),
+                    e
+                )
+            })?;
+            println!(
+                "📦 Your old {} was moved to {}, so nothing is overwritten.",
+                what,
+                moved.display()
+            );
+            Ok(None)
         }
-        println!("-------------------------");
     }
 }
 
 /*
-   save_expenses function:
-   - Serializes the 'expenses' vector into a pretty-formatted JSON string using serde_json.
-   - Creates (or overwrites) a file named "expenses.json" for storing the data.
-   - Writes the JSON string to the file as bytes.
-   - Prints a confirmation message upon successful saving.
+   write_companion Function:
+   - Writes `value` as pretty JSON to a file next to the data file with write_atomic, sealed
+     by storage.seal (encrypted when the data file is).
+   - With `empty` set (nothing to store) and no file there yet, writes nothing, so users who
+     never use a feature don't get an extra file; an existing file is still updated.
 */
-fn save_expenses(expenses: &Vec<Expense>) {
-    let json = serde_json::to_string_pretty(expenses).expect("Failed to serialize expenses");
-    let mut file = File::create("expenses.json").expect("Failed to create file");
-    file.write_all(json.as_bytes())
-        .expect("Failed to write to file");
-    println!("💾 Expenses saved successfully!");
+fn write_companion<T: Serialize + ?Sized>(
+    storage: &dyn Storage,
+    value: &T,
+    empty: bool,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if empty && !path.exists() {
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(value)?;
+    let contents = storage.seal(json.as_bytes())?;
+    write_atomic(path, &contents)
+        .map_err(|e| format!("could not write {}: {}", path.display(), e).into())
 }
 
-fn load_expenses() -> Vec<Expense> {
-    /*
-       Reads the "expenses.json" file and attempts to deserialize its contents into a vector of expenses.
+// Loads the budgets, overall monthly budget, recurring templates, trash and exchange rates kept next to the data file at `path`
+// (through the tracker's storage, so they are decrypted like the data file)
+fn load_companions(tracker: &mut ExpenseTracker, path: &str) -> Result<(), Box<dyn Error>> {
+    let storage = tracker.storage.as_ref();
+    tracker.budgets = load_budgets(storage, &companion_path(path, "budgets"))?;
+    tracker.monthly_budget = load_monthly_budget(storage, &companion_path(path, "monthly_budget"))?;
+    tracker.recurring = load_recurring(storage, &companion_path(path, "recurring"))?;
+    tracker.trash = load_trash(storage, &companion_path(path, "trash"))?;
+    tracker.rates = load_rates(storage, &companion_path(path, "rates"))?;
+    Ok(())
+}
 
-       Control Flow:
-       - If the file is read successfully (Ok(data)):
-           • Attempts to parse the JSON data.
-           • On parsing error, prints an error message and returns an empty vector.
-       - If the file is not found (ErrorKind::NotFound):
-           • Informs the user no previous expenses were found and returns an empty vector.
-       - For any other file read error:
-           • Prints a general error message and returns an empty vector.
-    */
-    match fs::read_to_string("expenses.json") {
-        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|_| {
-            println!("⚠️ Error parsing file. Starting fresh.");
-            Vec::new()
-        }),
-        Err(error) if error.kind() == ErrorKind::NotFound => {
-            println!("📂 No previous expenses found. Starting fresh.");
-            Vec::new()
+// Profile names end up in file names, so only letters, digits, `-` and `_` are allowed
+fn parse_profile(name: &str) -> Result<String, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err("use only letters, digits, '-' and '_'".to_string())
+    }
+}
+
+/*
+   profile_paths Function:
+   - Returns the (data file, config file) of a profile: `expenses_<profile>.json` and
+     `config_<profile>.json`. The default profile keeps expenses.json and config.json, so
+     existing data loads as before.
+   - Budgets, trash and recurring bills sit next to the data file, so they are per profile too.
+*/
+fn profile_paths(profile: &str) -> (String, String) {
+    if profile == DEFAULT_PROFILE {
+        ("expenses.json".to_string(), CONFIG_FILE.to_string())
+    } else {
+        (
+            format!("expenses_{}.json", profile),
+            format!("config_{}.json", profile),
+        )
+    }
+}
+
+/*
+   load_tracker Function:
+   - Builds a tracker from the config file at `config_path` and the data file at `data_path`.
+   - Also loads the budgets (including the overall monthly one), recurring templates, trash
+     and exchange rates stored next to the data file (not in pipeline mode) with
+     load_companions. If one of them can't be read, the program stops like it does for the
+     data file.
+   - Makes sure every expense has a unique ID and sets the ID counter past them.
+   - If config.default_sort is set, the loaded expenses are put in that order straight away,
+     so the first view already shows the preferred order (and it is kept on the next save).
+*/
+fn load_tracker(data_path: &str, config_path: &str) -> ExpenseTracker {
+    let mut tracker = ExpenseTracker::new();
+    tracker.config = load_config(config_path);
+    tracker.config_path = config_path.to_string();
+    tracker.storage = storage_for(data_path, &tracker.config);
+    tracker.expenses = load_expenses(tracker.storage.as_ref(), data_path);
+    if data_path != STDIO_PATH {
+        if let Err(e) = load_companions(&mut tracker, data_path) {
+            eprintln!(
+                "{}",
+                format!(
+                    "⚠️ Could not load the files next to {}: {}. Stopping so nothing is overwritten.",
+                    data_path, e
+                )
+                .red()
+            );
+            std::process::exit(1);
         }
-        Err(_) => {
-            println!("⚠️ Error reading file. Starting fresh.");
-            Vec::new()
-        } // Return empty list if file does not exist
     }
+    tracker.next_id = assign_missing_ids(&mut tracker.expenses, &mut tracker.trash);
+
+    if let Some(order) = tracker.config.default_sort {
+        apply_sort(&mut tracker.expenses, order);
+    }
+
+    tracker
 }
 
-fn monthly_summary(expenses: &Vec<Expense>) {
-    let now = Utc::now();
-    let current_month = now.month();
-    let current_year = now.year();
+fn main() {
+    let cli = Cli::parse();
+    let (profile_file, config_path) = profile_paths(&cli.profile);
+    let data_path = cli.file.unwrap_or(profile_file);
 
-    let mut category_totals: std::collections::HashMap<String, f64> =
-        std::collections::HashMap::new();
-    let mut total_spent = 0.0;
+    if let Some(command) = cli.command {
+        run_command(command, &data_path, &config_path);
+        return;
+    }
 
     /*
-       For each expense in the expenses list:
-       - Checks if the expense's timestamp matches the current month and year.
-       - If it matches:
-           • Updates category_totals:
-               - Uses .entry() with a cloned category string.
-               - Inserts 0.0 if the category is not present.
-               - Adds the expense amount to the existing total.
-           • Adds the expense amount to total_spent.
+       Pipeline Mode (`--file -`):
+       - stdin carries the dataset, so there is no terminal left to prompt on.
+       - Loads from stdin, writes the result to stdout and exits without the menu.
+       - Status messages go to stderr so stdout stays valid JSON for the next tool.
     */
-    for expense in expenses {
-        if expense.timestamp.month() == current_month && expense.timestamp.year() == current_year {
-            *category_totals
-                .entry(expense.category.clone())
-                .or_insert(0.0) += expense.amount;
-            total_spent += expense.amount;
+    if data_path == STDIO_PATH {
+        let tracker = load_tracker(&data_path, &config_path);
+        if let Err(e) = save_expenses(&tracker, &data_path) {
+            eprintln!("⚠️ Failed to write the expenses to stdout: {}", e);
+            std::process::exit(1);
         }
+        return;
     }
 
-    if category_totals.is_empty() {
-        println!("\n📂 No expenses recorded for this month.");
-        return;
+    println!("💰 Welcome to the Rust Expense Tracker!");
+    if cli.profile != DEFAULT_PROFILE {
+        println!("👤 Profile: {}", cli.profile.bold());
     }
 
-    println!(
-        "\n📊 Monthly Summary for {}/{}:",
-        current_month, current_year
+    let mut tracker = load_tracker(&data_path, &config_path);
+    post_due_recurring(&mut tracker, Utc::now().date_naive());
+    show_streak(&tracker);
+    if offer_month_end_report(&mut tracker).is_err() {
+        println!("↩️ Report skipped.");
+    }
+
+    /*
+       Main Loop:
+       - Defines menu choices (with emojis) for various expense tracker actions.
+       - Uses an interactive prompt (via Select) to capture the user's selection.
+       - Executes the corresponding function based on the choice
+       - Escape or Ctrl-C on the menu (or a lost terminal) saves and exits like "Save & Exit";
+         cancelling a prompt inside an action just returns to the menu.
+    */
+    loop {
+        let choices = vec![
+            "➕ Add Expense",
+            "📋 View Expenses",
+            "📊 Sort Expenses",
+            "📊 Filter Expenses",
+            "📅 Monthly Summary",
+            "⚠️ Budgets",
+            "🗑️ Delete an Expense",
+            "📁 Export Expenses",
+            "👥 Split an Expense",
+            "👥 Shared Expenses Summary",
+            "📁 Export Budgets",
+            "📂 Import Budgets",
+            "🧙 Budget Wizard",
+            "🔁 Copy Last Month's Entries",
+            "📦 Export to Parquet",
+            "🧹 Clean Up Uncategorized",
+            "💱 Convert Total",
+            "🏷️ Labels",
+            "♻️ Trash",
+            "✏️ Edit an Expense",
+            "💱 Set Exchange Rate",
+            "🔍 Search Expenses",
+            "📈 Statistics",
+            "🔔 Set Budget Warning Threshold",
+            "↩️ Undo Last Action",
+            "📥 Import from CSV",
+            "📆 Recurring Expenses",
+            "💳 Payment Method Summary",
+            "🗄️ Restore from Backup",
+            "🗓️ Yearly Summary",
+            "📅 Summary for a Date Range",
+            "📝 Export Monthly Summary",
+            "⚙️ Settings",
+            "🏆 Top Expenses",
+            "📋 Budget vs. Actual",
+            "🗓️ Weekly Summary",
+            "🔀 Merge Categories",
+            "✏️ Rename a Category",
+            "💾 Save",
+            "📆 Spending by Day of the Week",
+            "📉 This Month vs. Last Month",
+            "🗑️ Delete a Category",
+            "🔎 View Expense Details",
+            "🕒 Recent Activity",
+            "📈 Inflation-Adjusted Totals",
+            "💾 Save & Exit",
+        ];
+
+        let selection = match Select::new()
+            .with_prompt("📌 Choose an option")
+            .default(0)
+            .items(&choices)
+            .interact_opt()
+        {
+            Ok(Some(choice)) => choice,
+            Ok(None) | Err(_) => choices.len() - 1,
+        };
+
+        /*
+           This match block controls the program's flow based on the user's menu selection:
+           - 0: Call add_expense, passing a mutable reference to the tracker.
+           - 1: Call view_expenses_with_budgets, displaying the list of expenses and any overspent budgets.
+           - 2: Call sort_expenses to order the expenses.
+           - 3: Call filter_expenses to show the expenses of a category, date range or amount range.
+           - 4: Call monthly_summary to generate a report.
+           - 5: Call budgets_menu to set, list or remove budget limits.
+           - 6: Call delete_expenses to remove an expense.
+           - 7: Call export_menu to export expenses as CSV or a Markdown table.
+           - 8: Call split_expense to share an expense between several people.
+           - 9: Call shared_summary to show what each person owes.
+           - 10: Call export_budgets_menu to back up the budgets as CSV or JSON.
+           - 11: Call import_budgets_menu to load budgets from a CSV or JSON file.
+           - 12: Call budget_wizard to suggest budgets from past spending.
+           - 13: Call clone_last_month to copy last month's entries of chosen categories.
+           - 14: Export expenses to Parquet (only available in builds with the `parquet` feature).
+           - 15: Call cleanup_uncategorized to assign categories to blank/"Uncategorized" entries.
+           - 16: Call convert_total_menu to show the total spending in another currency.
+           - 17: Call labels_menu to label, filter or summarize expenses by label.
+           - 18: Call trash_menu to view, restore or empty soft-deleted expenses.
+           - 19: Call edit_expense to change a field of an existing expense.
+           - 20: Call set_exchange_rate to set a currency's rate for the monthly summary.
+           - 21: Call search_expenses to find expenses by a keyword in category or description.
+           - 22: Call statistics to show count, total, average, median, min and max of all expenses.
+           - 23: Call set_warn_threshold to change when the "nearing budget" warning kicks in.
+           - 24: Call undo_last_action to revert the last add, edit or delete.
+           - 25: Call import_csv_menu to append expenses from a CSV file (e.g. a bank export).
+           - 26: Call recurring_menu to add or list recurring monthly expenses.
+           - 27: Call payment_method_summary to total this month's spending per payment method.
+           - 28: Call restore_backup_menu to load one of the data file's backups.
+           - 29: Call yearly_summary to show this year's totals per month and per category.
+           - 30: Call range_summary_menu to summarize the expenses between two dates.
+           - 31: Call export_summary_menu to save this month's summary as a text or Markdown file.
+           - 32: Call settings_menu to change and save the default currency, warning threshold, page size, date formats and inflation index.
+           - 33: Call top_expenses_menu to list the N biggest expenses.
+           - 34: Call budget_report to compare this month's spending with every budget.
+           - 35: Call weekly_summary to show this week's category breakdown and totals.
+           - 36: Call merge_categories_menu to move every expense in one category to another.
+           - 37: Call rename_category_menu to rename a category on every expense and its budget.
+           - 38: Call save_now to save everything and return to the menu.
+           - 39: Call weekday_summary_menu to total the spending per day of the week.
+           - 40: Call month_comparison to show how each category's spending changed since last month.
+           - 41: Call delete_category_menu to delete every expense in a category (and optionally its budget).
+           - 42: Call view_details_menu to show one expense with all its fields, including the note.
+           - 43: Call recent_activity_menu to list the last N entries, newest first.
+           - 44: Call inflation_report_menu to total each year's spending in a reference year's prices.
+           - 45: Save expenses with save_before_exit (which offers a retry, another file, the menu or quitting without
+                 saving if that fails), then budgets, recurring templates and trash next to them, print a goodbye
+                 message, and break out of the loop to exit.
+           - _: Handle any invalid selection with a warning message.
+           Actions that prompt return a PromptResult; an Err means one of their prompts was
+           cancelled, which is reported before showing the menu again.
+        */
+        let result = match selection {
+            0 => add_expense(&mut tracker),
+            1 => {
+                view_expenses_with_budgets(&tracker);
+                Ok(())
+            }
+            2 => sort_expenses(
+                &mut tracker.expenses,
+                tracker.config.page_size,
+                &tracker.config.date_display_format,
+                tracker.config.rounding,
+            ),
+            3 => filter_expenses(
+                &tracker.expenses,
+                &tracker.config.date_input_format,
+                &tracker.config.date_display_format,
+                tracker.config.rounding,
+            ),
+            4 => {
+                monthly_summary(
+                    &tracker.expenses,
+                    &tracker.rates,
+                    &tracker.config,
+                    tracker.monthly_budget,
+                );
+                Ok(())
+            }
+            5 => budgets_menu(&mut tracker),
+            6 => delete_expenses(&mut tracker),
+            7 => export_menu(
+                &tracker.expenses.iter().collect::<Vec<&Expense>>(),
+                tracker.config.rounding,
+            ),
+            8 => split_expense(&mut tracker),
+            9 => {
+                shared_summary(&tracker.expenses, tracker.config.rounding);
+                Ok(())
+            }
+            10 => export_budgets_menu(&tracker.budgets),
+            11 => import_budgets_menu(&mut tracker),
+            12 => budget_wizard(&mut tracker),
+            13 => clone_last_month(&mut tracker),
+            14 => export_to_parquet_menu(&tracker.expenses),
+            15 => cleanup_uncategorized(&mut tracker),
+            16 => convert_total_menu(&tracker),
+            17 => labels_menu(&mut tracker),
+            18 => trash_menu(&mut tracker),
+            19 => edit_expense(&mut tracker),
+            20 => set_exchange_rate(&mut tracker),
+            21 => search_expenses(&tracker.expenses, &tracker.config.date_display_format),
+            22 => {
+                statistics(
+                    &tracker.expenses,
+                    &tracker.config.date_display_format,
+                    tracker.config.rounding,
+                );
+                Ok(())
+            }
+            23 => set_warn_threshold(&mut tracker),
+            24 => {
+                undo_last_action(&mut tracker);
+                Ok(())
+            }
+            25 => import_csv_menu(&mut tracker),
+            26 => recurring_menu(&mut tracker),
+            27 => {
+                payment_method_summary(&tracker.expenses, tracker.config.rounding);
+                Ok(())
+            }
+            28 => restore_backup_menu(&mut tracker, &data_path),
+            29 => {
+                yearly_summary(&tracker.expenses, tracker.config.rounding);
+                Ok(())
+            }
+            30 => range_summary_menu(&tracker),
+            31 => export_summary_menu(&tracker),
+            32 => settings_menu(&mut tracker),
+            33 => top_expenses_menu(&tracker.expenses, &tracker.config.date_display_format),
+            34 => {
+                budget_report(&tracker);
+                Ok(())
+            }
+            35 => {
+                weekly_summary(
+                    &tracker.expenses,
+                    &tracker.rates,
+                    tracker.config.percent_decimals,
+                    tracker.config.rounding,
+                );
+                Ok(())
+            }
+            36 => merge_categories_menu(&mut tracker),
+            37 => rename_category_menu(&mut tracker),
+            38 => {
+                save_now(&mut tracker, &data_path);
+                Ok(())
+            }
+            39 => weekday_summary_menu(&tracker.expenses, tracker.config.rounding),
+            40 => {
+                month_comparison(
+                    &tracker.expenses,
+                    tracker.config.percent_decimals,
+                    tracker.config.rounding,
+                );
+                Ok(())
+            }
+            41 => delete_category_menu(&mut tracker),
+            42 => view_details_menu(&tracker),
+            43 => recent_activity_menu(&tracker.expenses, &tracker.config.date_display_format),
+            44 => inflation_report_menu(&tracker),
+            45 => match save_before_exit(&tracker, &data_path) {
+                ExitSave::Saved => {
+                    println!("👋 Exiting program... Goodbye!");
+                    break;
+                }
+                ExitSave::Discard => {
+                    println!("👋 Exiting without saving... Goodbye!");
+                    break;
+                }
+                ExitSave::Stay => Ok(()),
+            },
+            _ => {
+                println!("⚠️ Invalid choice! Please try again.");
+                Ok(())
+            }
+        };
+
+        if result.is_err() {
+            println!("{}", "↩️ Cancelled. Back to the menu.".yellow());
+        }
+        if tracker.config.autosave && tracker.unsaved_changes {
+            autosave(&mut tracker, &data_path);
+        }
+    }
+}
+
+/*
+   run_command Function:
+   - Runs one subcommand against the data file and returns, without the interactive menu.
+   - Add builds the expense from the flags (the amount goes through validate_amount and the
+     date through parse_expense_timestamp), records it with record_expense and saves.
+     Invalid input is reported on stderr and exits with status 1.
+   - List, Summary and Export reuse view_expenses_paged (as a single page), monthly_summary
+     and the export functions.
+   - With `--file -`, Add is refused because stdout has to carry the JSON; the read-only
+     commands work on the piped data.
+*/
+fn run_command(command: Command, data_path: &str, config_path: &str) {
+    match command {
+        Command::Add {
+            amount,
+            category,
+            description,
+            currency,
+            date,
+            payment_method,
+            tags,
+            income,
+            note,
+        } => {
+            let mut tracker = load_tracker(data_path, config_path);
+            let amount = validate_amount(amount).unwrap_or_else(|e| {
+                eprintln!("⚠️ {}", e);
+                std::process::exit(1);
+            });
+            let timestamp =
+                parse_expense_timestamp(&date, &tracker.config.date_input_format, Utc::now())
+                    .unwrap_or_else(|e| {
+                        eprintln!("⚠️ {}", e);
+                        std::process::exit(1);
+                    });
+            if !is_plausible_date(timestamp) {
+                eprintln!(
+                    "⚠️ {} is more than {} day(s) in the future; check the date.",
+                    format_local(timestamp, &tracker.config.date_display_format),
+                    FUTURE_WARNING_DAYS
+                );
+            }
+            if let Err(e) = validate_category(&category) {
+                eprintln!("⚠️ {}", e);
+                std::process::exit(1);
+            }
+            let currency = currency.unwrap_or_else(|| tracker.config.default_currency.clone());
+
+            let messages = record_expense(
+                &mut tracker,
+                Expense {
+                    id: 0,
+                    amount,
+                    currency: currency.trim().to_uppercase(),
+                    kind: if income {
+                        EntryKind::Income
+                    } else {
+                        EntryKind::Expense
+                    },
+                    category: normalize_category(&category),
+                    description: description.trim().to_string(),
+                    timestamp,
+                    split: Vec::new(),
+                    payment_method: payment_method.trim().to_string(),
+                    tags: parse_comma_list(&tags),
+                    labels: Vec::new(),
+                    note: note.trim().to_string(),
+                },
+            );
+            // With `--file -` stdout carries the updated list, so the messages go to stderr
+            for message in messages {
+                if data_path == STDIO_PATH {
+                    eprintln!("{}", message);
+                } else {
+                    println!("{}", message);
+                }
+            }
+            if let Err(e) = save_expenses(&tracker, data_path) {
+                eprintln!("⚠️ Failed to save {}: {}", data_path, e);
+                std::process::exit(1);
+            }
+        }
+        Command::List => {
+            let tracker = load_tracker(data_path, config_path);
+            view_expenses(
+                &tracker.expenses,
+                usize::MAX,
+                &tracker.config.date_display_format,
+                tracker.config.rounding,
+            )
+        }
+        Command::Summary => {
+            let tracker = load_tracker(data_path, config_path);
+            monthly_summary(
+                &tracker.expenses,
+                &tracker.rates,
+                &tracker.config,
+                tracker.monthly_budget,
+            );
+        }
+        Command::Export {
+            format,
+            output,
+            totals,
+            columns,
+        } => {
+            let tracker = load_tracker(data_path, config_path);
+            let expenses: Vec<&Expense> = tracker.expenses.iter().collect();
+            let columns = match columns.as_deref().map(parse_csv_columns) {
+                None => CsvColumn::ALL.to_vec(),
+                Some(Ok(columns)) => columns,
+                Some(Err(e)) => {
+                    eprintln!("⚠️ {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let result = match format {
+                ExportFormat::Csv => export_to_csv(
+                    &expenses,
+                    output.as_deref().unwrap_or(CSV_FILE),
+                    &columns,
+                    totals,
+                    tracker.config.rounding,
+                ),
+                ExportFormat::Markdown => export_to_markdown(
+                    &expenses,
+                    output.as_deref().unwrap_or(MARKDOWN_FILE),
+                    tracker.config.rounding,
+                ),
+                ExportFormat::Json => {
+                    export_to_json(&expenses, output.as_deref().unwrap_or(JSON_EXPORT_FILE))
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("⚠️ Failed to export: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+// Function to add an expense
+fn add_expense(tracker: &mut ExpenseTracker) -> PromptResult {
+    let kinds = vec!["💸 Expense", "💵 Income"];
+    let kind = match Select::new()
+        .with_prompt("Is this an expense or income?")
+        .default(0)
+        .items(&kinds)
+        .interact()?
+    {
+        0 => EntryKind::Expense,
+        _ => EntryKind::Income,
+    };
+
+    let category = prompt_category(&tracker.expenses, tracker.last_category.as_deref())?;
+
+    let description: String = Input::new()
+        .with_prompt("Enter a description (optional):")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let tags: String = Input::new()
+        .with_prompt("Enter tags, comma-separated (optional):")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let note: String = Input::new()
+        .with_prompt("Enter a note (optional):")
+        .allow_empty(true)
+        .interact_text()?;
+
+    // validate_amount rejects zero and negative values; dialoguer re-prompts until it passes.
+    let amount: f64 = Input::new()
+        .with_prompt("Enter expense amount:")
+        .validate_with(|amount: &f64| validate_amount(*amount).map(|_| ()))
+        .interact_text()?;
+
+    if let Some(position) = find_duplicate(&tracker.expenses, &category, amount, Utc::now()) {
+        let existing = &tracker.expenses[position];
+        let add_anyway = Confirm::new()
+            .with_prompt(format!(
+                "This looks like a duplicate of #{} added at {} — add anyway?",
+                position + 1,
+                format_local(existing.timestamp, &tracker.config.date_display_format)
+            ))
+            .default(false)
+            .interact()?;
+        if !add_anyway {
+            println!("👍 Not added.");
+            return Ok(());
+        }
+    }
+
+    let currency: String = Input::new()
+        .with_prompt("Enter currency code:")
+        .default(tracker.config.default_currency.clone())
+        .interact_text()?;
+    let currency = currency.trim().to_uppercase();
+
+    let payment_method: String = Input::new()
+        .with_prompt("Enter payment method (e.g. Cash, Visa):")
+        .default(UNKNOWN_PAYMENT_METHOD.to_string())
+        .interact_text()?;
+    let payment_method = match payment_method.trim() {
+        "" => default_payment_method(),
+        method => method.to_string(),
+    };
+
+    // Invalid dates are rejected by parse_expense_timestamp and dialoguer asks again.
+    let date_format = &tracker.config.date_input_format;
+    let date_input: String = Input::new()
+        .with_prompt(format!(
+            "Enter expense date ({}, leave blank for now):",
+            date_format_hint(date_format)
+        ))
+        .allow_empty(true)
+        .validate_with(|input: &String| {
+            parse_expense_timestamp(input, date_format, Utc::now()).map(|_| ())
+        })
+        .interact_text()?;
+    let timestamp = parse_expense_timestamp(&date_input, date_format, Utc::now()).unwrap();
+    if !confirm_future_date(timestamp, &tracker.config.date_display_format)? {
+        println!("👍 Not added.");
+        return Ok(());
+    }
+
+    /*
+       Builds the new expense entry and hands it to record_expense:
+       - id: Left at 0; record_expense assigns the next ID from the tracker's counter.
+       - category: Clones the category string to ensure ownership.
+       - description: The trimmed description, empty if none was given.
+       - amount: Uses the provided expense value (f64) for calculations.
+       - currency: The entered currency code in upper case.
+       - kind: Expense or Income, as chosen first.
+       - timestamp: The entered date, or the current UTC time using chrono::Utc::now().
+       - payment_method: The entered payment method, "Unknown" if left blank or at the default.
+       - tags: The entered tags, split with parse_comma_list.
+       - labels: Empty; labels are attached later with review_labels.
+       - note: The trimmed note, empty if none was given.
+    */
+    tracker.last_category = Some(category.clone());
+    let messages = record_expense(
+        tracker,
+        Expense {
+            id: 0,
+            category,
+            description: description.trim().to_string(),
+            amount,
+            currency,
+            kind,
+            timestamp,
+            split: Vec::new(),
+            payment_method,
+            tags: parse_comma_list(&tags),
+            labels: Vec::new(),
+            note: note.trim().to_string(),
+        },
     );
-    println!("-------------------------------------");
+    for message in messages {
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+/*
+   find_duplicate Function:
+   - Looks for an entry with the same category (ignoring case and surrounding spaces) and the
+     same amount, dated within DUPLICATE_WINDOW_MINUTES before `now`.
+   - Returns the list position of the most recent match, so add_expense can ask before
+     logging the same purchase twice.
+*/
+fn find_duplicate(
+    expenses: &[Expense],
+    category: &str,
+    amount: f64,
+    now: DateTime<Utc>,
+) -> Option<usize> {
+    let window = chrono::Duration::minutes(DUPLICATE_WINDOW_MINUTES);
+    expenses
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| {
+            let age = now - e.timestamp;
+            e.category.trim().eq_ignore_ascii_case(category.trim())
+                && (e.amount - amount).abs() < 0.005
+                && age >= chrono::Duration::zero()
+                && age <= window
+        })
+        .max_by_key(|(_, e)| e.timestamp)
+        .map(|(position, _)| position)
+}
+
+/*
+   record_expense Function:
+   - Adds the expense through ExpenseTracker::add (new ID, undo point).
+   - Returns the confirmation of what was added and, for expenses (not income), the
+     budget_lines and monthly_budget_lines for it.
+   - Shared by the interactive add_expense and the `add` subcommand; the caller prints the
+     lines, so the subcommand can send them to stderr when stdout carries the data.
+*/
+fn record_expense(tracker: &mut ExpenseTracker, expense: Expense) -> Vec<String> {
+    let category = expense.category.clone();
+    let line = format!(
+        "{} - {}",
+        category,
+        format_money(expense.amount, &expense.currency)
+    );
+    let income = expense.is_income();
+    tracker.add(expense);
 
-    for (category, total) in &category_totals {
-        println!("Category: {}, Total Spent: ${:.2}", category, total);
+    if income {
+        return vec![format!("✅ Income added: {}", line)];
     }
 
-    println!("-------------------------------------");
-    println!("💰 Total Spending This Month: ${:.2}", total_spent);
+    let mut messages = vec![format!("✅ Expense added: {}", line)];
+    messages.extend(budget_lines(tracker, &category));
+    messages.extend(monthly_budget_lines(tracker));
+    messages
+}
+
+// Accepts only finite amounts greater than zero; returns the amount back so it can be chained
+fn validate_amount(amount: f64) -> Result<f64, String> {
+    if !amount.is_finite() {
+        return Err("Amount must be a valid number.".to_string());
+    }
+    if amount <= 0.0 {
+        return Err(format!(
+            "Amount must be greater than zero (got {}).",
+            amount
+        ));
+    }
+    Ok(amount)
 }
 
 /*
-   set_budget Function:
-   - Prompts the user to enter a category to set a budget for.
-   - Prompts the user to input the budget limit for that category.
-   - Inserts the category and its budget into the tracker’s budgets (a HashMap).
-   - Prints a confirmation message showing the budget set.
+   prompt_category Function:
+   - Offers the distinct categories already in use (sorted, from CategoryCompletion) in a
+     Select, with "➕ New category" at the end; with no expenses yet it goes straight to typing.
+   - A new category is typed in with Tab completion of the known ones, must not be blank,
+     and goes through normalize_category.
+   - `last` is the category used for the previous entry this session; it is pre-selected and
+     named in the prompt ("Choose a category [Food]"), so Enter alone reuses it.
 */
-fn set_budget(tracker: &mut ExpenseTracker) {
+fn prompt_category(expenses: &[Expense], last: Option<&str>) -> PromptResult<String> {
+    let completion = CategoryCompletion::from_expenses(expenses);
+    if !completion.categories.is_empty() {
+        let mut options: Vec<&str> = completion.categories.iter().map(String::as_str).collect();
+        options.push("➕ New category");
+        let last_position = last.and_then(|last| options.iter().position(|c| *c == last));
+        let prompt = match last_position {
+            Some(position) => format!("Choose a category [{}]", options[position]),
+            None => "Choose a category".to_string(),
+        };
+        let choice = Select::new()
+            .with_prompt(prompt)
+            .default(last_position.unwrap_or(0))
+            .items(&options)
+            .interact()?;
+        if let Some(category) = completion.categories.get(choice) {
+            return Ok(category.clone());
+        }
+    }
+
     let category: String = Input::new()
-        .with_prompt("Enter category name to set a budget for")
-        .interact_text()
-        .unwrap();
+        .with_prompt("Enter expense category:")
+        .completion_with(&completion)
+        .validate_with(|category: &String| validate_category(category))
+        .interact_text()?;
+    Ok(normalize_category(&category))
+}
 
-    let budget: f64 = Input::new()
-        .with_prompt(format!("Enter budget limit for '{}'", category))
-        .interact_text()
-        .unwrap();
+// Rejects a category that is empty or only whitespace; dialoguer re-prompts until it passes
+fn validate_category(input: &str) -> Result<(), &'static str> {
+    if input.trim().is_empty() {
+        Err("The category can't be empty.")
+    } else {
+        Ok(())
+    }
+}
 
-    tracker.budgets.insert(category.clone(), budget);
-    println!(
-        "✅ Budget of ${:.2} set for category '{}'",
-        budget, category
-    );
+/*
+   normalize_category Function:
+   - Trims the category, collapses runs of whitespace to one space and title-cases every
+     word, so "food", "Food" and " FOOD " all become "Food" (and "eating  out" "Eating Out").
+   - Applied wherever a category is typed in, so budgets and filters see one spelling.
+*/
+fn normalize_category(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
-fn export_to_csv(expenses: &Vec<Expense>) -> Result<(), Box<dyn Error>> {
-    let mut wtr = Writer::from_writer(File::create("expense_csv")?);
+/*
+   pick_expense Function:
+   - Asks whether to pick an expense by its list number or by its ID, then asks for it.
+   - Returns the expense's position in `expenses`, or None (after a message) if there is
+     no such expense. Err if a prompt was cancelled.
+*/
+fn pick_expense(expenses: &[Expense]) -> PromptResult<Option<usize>> {
+    let modes = vec!["By list number", "By ID"];
+    let by_id = Select::new()
+        .with_prompt("Pick the expense")
+        .default(0)
+        .items(&modes)
+        .interact()?
+        == 1;
 
-    // Write CSV headers
-    wtr.write_record(&["Category", "Amount", "Timestamp"])?;
+    if by_id {
+        let id: u64 = Input::new()
+            .with_prompt("Enter the expense ID")
+            .interact_text()?;
+        let position = expenses.iter().position(|e| e.id == id);
+        if position.is_none() {
+            println!("⚠️ No expense with ID {}.", id);
+        }
+        Ok(position)
+    } else {
+        let number: usize = Input::new()
+            .with_prompt("Enter the expense number")
+            .interact_text()?;
+        let position = list_position(number, expenses.len());
+        if position.is_none() {
+            println!("⚠️ Invalid number! There is no expense #{}.", number);
+        }
+        Ok(position)
+    }
+}
 
-    /*
-       Iterates over each expense in the expenses vector and writes its data as a CSV record:
-       - expense.category: Directly written as the category string.
-       - expense.amount.to_string(): Converts the amount (f64) to a string.
-       - expense.timestamp.to_string(): Converts the timestamp to a string.
-       The '?' operator propagates any errors that occur during writing.
-    */
-    for expense in expenses {
-        wtr.write_record(&[
-            &expense.category,
-            &expense.amount.to_string(),
-            &expense.timestamp.to_string(),
-        ])?;
+/*
+   edit_expense Function:
+   - Shows the expense list and lets the user pick one with pick_expense.
+   - Asks which field to change (amount, category, description, date or note) and prompts
+     for the new value, prefilled with the current one.
+   - New amounts go through validate_amount, like new expenses.
+   - Dates are read with the configured input format; a mismatch leaves the expense unchanged.
+   - After changing the amount or category, shows budget_lines and monthly_budget_lines for
+     the (new) category.
+*/
+fn edit_expense(tracker: &mut ExpenseTracker) -> PromptResult {
+    if tracker.expenses.is_empty() {
+        println!("\n❌ No expenses to edit!");
+        return Ok(());
     }
 
-    wtr.flush()?;
-    println!("📁 Expenses exported to `expenses.csv` successfully!");
+    view_expenses(
+        &tracker.expenses,
+        tracker.config.page_size,
+        &tracker.config.date_display_format,
+        tracker.config.rounding,
+    );
+    let Some(index) = pick_expense(&tracker.expenses)? else {
+        return Ok(());
+    };
+
+    let fields = vec!["Amount", "Category", "Description", "Date", "Note"];
+    let field = Select::new()
+        .with_prompt("Which field do you want to change?")
+        .default(0)
+        .items(&fields)
+        .interact()?;
+
+    let date_format = &tracker.config.date_input_format;
+    let expense = &tracker.expenses[index];
+    let edit = match field {
+        0 => ExpenseEdit::Amount(
+            Input::new()
+                .with_prompt("Enter the new amount")
+                .default(expense.amount)
+                .validate_with(|amount: &f64| validate_amount(*amount).map(|_| ()))
+                .interact_text()?,
+        ),
+        1 => ExpenseEdit::Category(
+            Input::new()
+                .with_prompt("Enter the new category")
+                .default(expense.category.clone())
+                .validate_with(|category: &String| validate_category(category))
+                .interact_text()?,
+        ),
+        2 => ExpenseEdit::Description(
+            Input::new()
+                .with_prompt("Enter the new description (blank to clear)")
+                .with_initial_text(expense.description.clone())
+                .allow_empty(true)
+                .interact_text()?,
+        ),
+        4 => ExpenseEdit::Note(
+            Input::new()
+                .with_prompt("Enter the new note (blank to clear)")
+                .with_initial_text(expense.note.clone())
+                .allow_empty(true)
+                .interact_text()?,
+        ),
+        _ => {
+            let input: String = Input::new()
+                .with_prompt(format!(
+                    "Enter the new date ({})",
+                    date_format_hint(date_format)
+                ))
+                .interact_text()?;
+            match parse_expense_timestamp(&input, date_format, Utc::now()) {
+                Ok(timestamp) => {
+                    if !confirm_future_date(timestamp, &tracker.config.date_display_format)? {
+                        println!("👍 Expense unchanged.");
+                        return Ok(());
+                    }
+                    ExpenseEdit::Date(timestamp)
+                }
+                Err(e) => {
+                    println!("⚠️ {} Expense unchanged.", e);
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    if let Err(e) = tracker.edit(index, edit) {
+        println!("⚠️ {} Expense unchanged.", e);
+        return Ok(());
+    }
+
+    let expense = &tracker.expenses[index];
+    println!("✅ Expense updated:");
+    print_expense_line(index + 1, expense, &tracker.config.date_display_format);
+    let category = expense.category.clone();
+
+    if field <= 1 {
+        for line in budget_lines(tracker, &category)
+            .into_iter()
+            .chain(monthly_budget_lines(tracker))
+        {
+            println!("{}", line);
+        }
+    }
     Ok(())
 }
 
 /*
-   delete_expenses Function:
-   - Checks if the expenses list is empty; if so, prints a message and exits.
-   - Prompts the user to enter the index of an expense to delete.
-   - Displays the current list of expenses using view_expenses.
-   - Reads user input as a string and attempts to parse it into a usize index.
-   - If parsing fails, prints an error and returns.
-   - Adjusts for 1-based user input by removing the expense at (index - 1) if the index is valid.
-   - Prints a success message on deletion, or an error if the index is out of range.
+   view_details_menu Function:
+   - Lets the user pick one expense by list number or ID (via pick_expense) and shows
+     every field of it with print_expense_details, including the note the lists leave out.
 */
-fn delete_expenses(expenses: &mut Vec<Expense>) {
-    if expenses.is_empty() {
-        println!("\n❌ No expenses to delete!");
-        return;
+fn view_details_menu(tracker: &ExpenseTracker) -> PromptResult {
+    if tracker.expenses.is_empty() {
+        println!("\n❌ No expenses to show!");
+        return Ok(());
     }
 
-    println!("\n 🗑️ Delete an Expense:");
-    view_expenses(expenses);
+    view_expenses(
+        &tracker.expenses,
+        tracker.config.page_size,
+        &tracker.config.date_display_format,
+        tracker.config.rounding,
+    );
+    let Some(index) = pick_expense(&tracker.expenses)? else {
+        return Ok(());
+    };
+    print_expense_details(
+        &tracker.expenses[index],
+        &tracker.config.date_display_format,
+    );
+    Ok(())
+}
+
+/*
+   print_expense_details Function:
+   - Prints one expense as a block with one labelled line per field: ID, category, kind,
+     amount, currency, local date, description, tags, labels, payment method, split and note.
+   - Empty fields show as "-" so the block always has the same shape.
+*/
+fn print_expense_details(expense: &Expense, display_format: &str) {
+    let or_dash = |text: String| {
+        if text.is_empty() {
+            "-".to_string()
+        } else {
+            text
+        }
+    };
+    let split = expense
+        .split
+        .iter()
+        .map(|share| format!("{} {:.0}%", share.person, share.ratio * 100.0))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let kind = if expense.is_income() {
+        "Income"
+    } else {
+        "Expense"
+    };
 
-    println!("\nEnter the index of the expense to delete:");
+    println!("\n🔎 Expense details:");
+    println!("   {:<16}{}", "ID:", expense.id);
+    println!("   {:<16}{}", "Category:", expense.category.green());
+    println!("   {:<16}{}", "Kind:", kind);
+    println!(
+        "   {:<16}{}",
+        "Amount:",
+        format_money(expense.amount, &expense.currency)
+    );
+    println!("   {:<16}{}", "Currency:", expense.currency);
+    println!(
+        "   {:<16}{}",
+        "Date:",
+        format_local(expense.timestamp, display_format).purple()
+    );
+    println!(
+        "   {:<16}{}",
+        "Description:",
+        or_dash(expense.description.clone())
+    );
+    println!("   {:<16}{}", "Tags:", or_dash(expense.tags.join(", ")));
+    println!("   {:<16}{}", "Labels:", or_dash(expense.labels.join(", ")));
+    println!("   {:<16}{}", "Payment method:", expense.payment_method);
+    println!("   {:<16}{}", "Split:", or_dash(split));
+    println!("   {:<16}{}", "Note:", or_dash(expense.note.clone()));
+}
 
-    let mut index_str = String::new();
-    io::stdin()
-        .read_line(&mut index_str)
-        .expect("Failed to read user input");
-    let index: usize = match index_str.trim().parse() {
-        Ok(num) => num,
-        Err(_) => {
-            println!("⚠️ Invalid input! Please enter a valid index.");
-            return;
+/*
+   budget_lines Function:
+   Checks if a budget exists for the given category and returns the lines to show about it
+   (none without a budget). Used after adding an expense and after editing one's amount or
+   category.
+
+   - `if let Some(&budget) = tracker.budgets.get(category)`:
+       Attempts to retrieve the budget for the category.
+       If found, destructures the value (using & to dereference) into `budget`.
+
+   - Gets the category's spending this calendar month from tracker.spent_in, so earlier
+     months no longer count against the budget (same window as monthly_summary).
+
+   - For a spending limit, reports what is left of it (negative and red once over).
+
+   - Lets budget_warning compare `total_spent` with the budget:
+       Adds its warning message, if any.
+*/
+fn budget_lines(tracker: &ExpenseTracker, category: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(&budget) = tracker.budgets.get(category) {
+        let now = Utc::now();
+        let total_spent = tracker.spent_in(category, now.year(), now.month());
+
+        if let (BudgetKind::Limit, limit) = BudgetKind::of(budget) {
+            let remaining = limit - total_spent;
+            let line = format!(
+                "Remaining budget for '{}': {}",
+                category,
+                format_money(remaining, &tracker.config.default_currency)
+            );
+            if remaining < 0.0 {
+                lines.push(line.red().to_string());
+            } else {
+                lines.push(line);
+            }
         }
+
+        if let Some(warning) = budget_warning(
+            category,
+            budget,
+            total_spent,
+            tracker.config.warn_threshold,
+            &tracker.config.default_currency,
+        ) {
+            lines.push(warning);
+        }
+    }
+    lines
+}
+
+/*
+   monthly_budget_lines Function:
+   - Returns nothing unless an overall monthly budget is set.
+   - Otherwise compares this month's spending in all categories (tracker.spent_overall) with it,
+     returning what is left and the same kind of warnings as a category limit (see budget_status).
+*/
+fn monthly_budget_lines(tracker: &ExpenseTracker) -> Vec<String> {
+    let Some(limit) = tracker.monthly_budget else {
+        return Vec::new();
     };
+    let now = Utc::now();
+    let spent = tracker.spent_overall(now.year(), now.month());
 
-    if index < expenses.len() {
-        expenses.remove(index - 1);
-        println!("✅ Expense deleted successfully!");
+    let money = |amount: f64| format_money(amount, &tracker.config.default_currency);
+    let remaining = limit - spent;
+    let line = format!("Remaining overall budget this month: {}", money(remaining));
+    let mut lines = vec![if remaining < 0.0 {
+        line.red().to_string()
+    } else {
+        line
+    }];
+
+    match budget_status(spent, limit, tracker.config.warn_threshold) {
+        BudgetStatus::Over => lines.push(
+            format!(
+                "⚠️ Warning: You have exceeded your overall monthly budget of {}.",
+                money(limit)
+            )
+            .red()
+            .to_string(),
+        ),
+        BudgetStatus::Near => lines.push(
+            format!(
+                "🔔 Heads up: You have spent {} of your {} overall monthly budget.",
+                money(spent),
+                money(limit)
+            )
+            .yellow()
+            .to_string(),
+        ),
+        BudgetStatus::Under => {}
+    }
+    lines
+}
+
+/*
+   budget_warning Function:
+   - Limit budgets: returns a red warning once `spent` is above the limit, and a yellow
+     one when it has reached `threshold` of the limit (see budget_status).
+   - Savings targets: returns a warning while `spent` (the contributions) is below the target.
+   - Returns None when the budget is being met. Amounts are shown in `currency`.
+*/
+fn budget_warning(
+    category: &str,
+    budget: f64,
+    spent: f64,
+    threshold: f64,
+    currency: &str,
+) -> Option<String> {
+    let money = |amount: f64| format_money(amount, currency);
+    match BudgetKind::of(budget) {
+        (BudgetKind::Limit, limit) => match budget_status(spent, limit, threshold) {
+            BudgetStatus::Over => Some(
+                format!(
+                    "⚠️ Warning: You have exceeded your budget of {} for '{}'.",
+                    money(limit),
+                    category
+                )
+                .red()
+                .to_string(),
+            ),
+            BudgetStatus::Near => Some(
+                format!(
+                    "🔔 Heads up: You have spent {} of your {} budget for '{}'.",
+                    money(spent),
+                    money(limit),
+                    category
+                )
+                .yellow()
+                .to_string(),
+            ),
+            BudgetStatus::Under => None,
+        },
+        (BudgetKind::Savings, target) if spent < target => Some(format!(
+            "⚠️ Warning: You have set aside {} for '{}', below your savings target of {}.",
+            money(spent),
+            category,
+            money(target)
+        )),
+        _ => None,
+    }
+}
+
+// Where `spent` stands against a limit: Over above it, Near from `threshold` × limit, else Under
+fn budget_status(spent: f64, limit: f64, threshold: f64) -> BudgetStatus {
+    if spent > limit {
+        BudgetStatus::Over
+    } else if spent >= limit * threshold {
+        BudgetStatus::Near
     } else {
-        println!("⚠️ Invalid index! No expense deleted.");
+        BudgetStatus::Under
+    }
+}
+
+// Function to revert the last add, edit or delete, if there is one
+fn undo_last_action(tracker: &mut ExpenseTracker) {
+    match tracker.undo_last() {
+        Some(action) => println!("↩️ Undid the last {}.", action),
+        None => println!("⚠️ Nothing to undo."),
+    }
+}
+
+// Function to set the percentage of a budget at which adding an expense starts to warn (saved to the config)
+fn set_warn_threshold(tracker: &mut ExpenseTracker) -> PromptResult {
+    let percent: f64 = Input::new()
+        .with_prompt(format!(
+            "Warn at what percent of a budget? (currently {})",
+            format_percent(tracker.config.warn_threshold * 100.0, 0)
+        ))
+        .validate_with(|p: &f64| {
+            if p.is_finite() && *p > 0.0 && *p <= 100.0 {
+                Ok(())
+            } else {
+                Err("Enter a percentage between 1 and 100.")
+            }
+        })
+        .interact_text()?;
+
+    tracker.config.warn_threshold = percent / 100.0;
+    println!(
+        "✅ You'll be warned once a category reaches {} of its budget.",
+        format_percent(percent, 0)
+    );
+    store_config(&tracker.config, &tracker.config_path);
+    Ok(())
+}
+
+/*
+   settings_menu Function:
+   - Lets the user change the default currency, the budget warning threshold, the page size
+     of the expense list, the date input format, the date display format and the inflation
+     index, one at a time until "Back".
+   - Every change is saved to the profile's config file right away (see store_config).
+   - A date input format is only accepted if today's date survives formatting and parsing
+     with it; a display format only needs to be valid (see check_display_format).
+*/
+fn settings_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    loop {
+        let config = &tracker.config;
+        let options = vec![
+            format!("Default currency ({})", config.default_currency),
+            format!(
+                "Budget warning threshold ({})",
+                format_percent(config.warn_threshold * 100.0, 0)
+            ),
+            format!("Page size ({})", config.page_size),
+            format!("Date input format ({})", config.date_input_format),
+            format!(
+                "Date display format ({}, e.g. {})",
+                config.date_display_format,
+                format_local(Utc::now(), &config.date_display_format)
+            ),
+            format!("Inflation index ({} year(s))", config.inflation_index.len()),
+            "Back".to_string(),
+        ];
+        let choice = Select::new()
+            .with_prompt("⚙️ Settings")
+            .default(0)
+            .items(&options)
+            .interact()?;
+
+        match choice {
+            0 => {
+                let currency: String = Input::new()
+                    .with_prompt("Default currency code")
+                    .default(tracker.config.default_currency.clone())
+                    .validate_with(|code: &String| {
+                        if code.trim().is_empty() {
+                            Err("The currency code can't be empty.")
+                        } else {
+                            Ok(())
+                        }
+                    })
+                    .interact_text()?;
+                tracker.config.default_currency = currency.trim().to_uppercase();
+                store_config(&tracker.config, &tracker.config_path);
+            }
+            1 => set_warn_threshold(tracker)?,
+            2 => {
+                tracker.config.page_size = Input::new()
+                    .with_prompt("Expenses per page")
+                    .default(tracker.config.page_size)
+                    .validate_with(|size: &usize| {
+                        if *size == 0 {
+                            Err("The page size must be at least 1.")
+                        } else {
+                            Ok(())
+                        }
+                    })
+                    .interact_text()?;
+                store_config(&tracker.config, &tracker.config_path);
+            }
+            3 => {
+                let today = Utc::now().date_naive();
+                let format: String = Input::new()
+                    .with_prompt("Date input format (chrono syntax, e.g. %d/%m/%Y)")
+                    .default(tracker.config.date_input_format.clone())
+                    .validate_with(|format: &String| check_date_format(format, today))
+                    .interact_text()?;
+                tracker.config.date_input_format = format.trim().to_string();
+                store_config(&tracker.config, &tracker.config_path);
+            }
+            4 => {
+                let format: String = Input::new()
+                    .with_prompt("Date display format (chrono syntax, e.g. %b %-d, %Y)")
+                    .default(tracker.config.date_display_format.clone())
+                    .validate_with(|format: &String| check_display_format(format))
+                    .interact_text()?;
+                tracker.config.date_display_format = format.to_string();
+                store_config(&tracker.config, &tracker.config_path);
+            }
+            5 => set_inflation_index(tracker)?,
+            _ => return Ok(()),
+        }
+    }
+}
+
+/*
+   set_inflation_index Function:
+   - Lists the years that have an index, then asks for a year and its index value.
+   - An index of 0 removes the year; negative values are rejected.
+   - Saves the config right away, like the other settings.
+*/
+fn set_inflation_index(tracker: &mut ExpenseTracker) -> PromptResult {
+    let index = &tracker.config.inflation_index;
+    if index.is_empty() {
+        println!("\n📈 No inflation index set yet.");
+    } else {
+        println!("\n📈 Inflation index:");
+        for (year, value) in index {
+            println!("   {}: {}", year, value);
+        }
+    }
+
+    let year: i32 = Input::new()
+        .with_prompt("Year")
+        .default(Utc::now().year())
+        .interact_text()?;
+    let value: f64 = Input::new()
+        .with_prompt(format!("Index for {} (e.g. the CPI, 0 to remove)", year))
+        .validate_with(|value: &f64| {
+            if value.is_finite() && *value >= 0.0 {
+                Ok(())
+            } else {
+                Err("Enter a positive index, or 0 to remove the year.")
+            }
+        })
+        .interact_text()?;
+
+    if value == 0.0 {
+        if tracker.config.inflation_index.remove(&year).is_none() {
+            println!("⚠️ {} had no index.", year);
+            return Ok(());
+        }
+        println!("✅ Index for {} removed.", year);
+    } else {
+        tracker.config.inflation_index.insert(year, value);
+        println!("✅ Index for {} set to {}.", year, value);
+    }
+    store_config(&tracker.config, &tracker.config_path);
+    Ok(())
+}
+
+// Checks that a chrono date format can write `today` and read it back unchanged
+fn check_date_format(format: &str, today: NaiveDate) -> Result<(), String> {
+    let mut formatted = String::new();
+    let written = write!(formatted, "{}", today.format(format.trim())).is_ok();
+    match written.then(|| parse_input_date(&formatted, format.trim())) {
+        Some(Ok(date)) if date == today => Ok(()),
+        _ => Err(format!("'{}' is not a usable date format.", format.trim())),
+    }
+}
+
+// Checks that a chrono format string is non-empty and has no unknown or incomplete specifiers
+fn check_display_format(format: &str) -> Result<(), String> {
+    let valid = !format.trim().is_empty()
+        && StrftimeItems::new(format).all(|item| !matches!(item, Item::Error));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid date format.", format))
+    }
+}
+
+// Saves the config to `path`, reporting a failure instead of stopping
+fn store_config(config: &Config, path: &str) {
+    match save_config(config, path) {
+        Ok(()) => println!("💾 Settings saved."),
+        Err(e) => println!("⚠️ Could not save {}: {}", path, e),
+    }
+}
+
+/*
+   view_expenses Function:
+   - Displays the list of recorded expenses in a formatted manner.
+   - Steps:
+       1. Prints a header ("Expense List") with bold and underline formatting.
+       2. Checks if there are any expenses:
+            • If empty, prints a warning and a "0 expenses" footer, then exits the function.
+       3. Otherwise, prints a sub-header ("Your Expenses") and a divider.
+       4. Iterates through expenses with enumeration:
+            • Formats and prints each expense with its index, category, timestamp, and amount.
+            • Lists longer than `page_size` (config.page_size) are shown a page at a time.
+       5. Ends by printing a closing divider and a footer with the count and total shown (see list_footer).
+   - After each page asks to go to the next or previous page, or to stop.
+   - Numbers keep counting across pages (page 2 starts at #21 with 20 per page), so they
+     still match the numbers used to delete or edit an expense.
+   - Lists that fit on one page are printed without any prompt.
+*/
+fn view_expenses(expenses: &[Expense], page_size: usize, display_format: &str, rounding: Rounding) {
+    println!("\n{}", "📋 Expense List".bold().underline());
+
+    if expenses.is_empty() {
+        println!("{}", "⚠️ No expenses recorded yet.".yellow());
+        println!("{}", list_footer(&[], rounding));
+        return;
+    }
+
+    println!("\n💰 Your Expenses:");
+
+    let page_size = page_size.max(1);
+    let pages = expenses.len().div_ceil(page_size);
+    let mut page = 0;
+    loop {
+        println!("-------------------------");
+        let start = page * page_size;
+        for (i, expense) in expenses.iter().enumerate().skip(start).take(page_size) {
+            print_expense_line(i + 1, expense, display_format);
+        }
+        println!("-------------------------");
+        let shown: Vec<&Expense> = expenses.iter().skip(start).take(page_size).collect();
+        println!("{}", list_footer(&shown, rounding));
+
+        if pages == 1 {
+            return;
+        }
+        println!("Page {}/{}", page + 1, pages);
+
+        let mut options: Vec<(&str, Option<usize>)> = Vec::new();
+        if page + 1 < pages {
+            options.push(("➡️ Next page", Some(page + 1)));
+        }
+        if page > 0 {
+            options.push(("⬅️ Previous page", Some(page - 1)));
+        }
+        options.push(("✅ Done", None));
+
+        let labels: Vec<&str> = options.iter().map(|(label, _)| *label).collect();
+        // Cancelling the pager just stops paging, like choosing "Done".
+        let Ok(choice) = Select::new().default(0).items(&labels).interact() else {
+            return;
+        };
+        match options[choice].1 {
+            Some(next) => page = next,
+            None => return,
+        }
+    }
+}
+
+// "3 expenses, total $45.00" for the entries on screen; income is counted but left out of the total
+fn list_footer(shown: &[&Expense], rounding: Rounding) -> String {
+    let noun = if shown.len() == 1 {
+        "expense"
+    } else {
+        "expenses"
+    };
+    let total = if shown.iter().all(|e| e.is_income()) {
+        format_money(0.0, BASE_CURRENCY)
+    } else {
+        format_totals(shown, rounding)
+    };
+    format!("{} {}, total {}", shown.len(), noun, total.bold())
+}
+
+/*
+   format_money Function:
+   - Formats an amount with two decimals and its currency.
+   - Common currencies use their symbol in front ("$12.50", "€12.50", "£12.50", "¥12.50"),
+     any other code goes after the amount ("12.50 CHF").
+*/
+fn format_money(amount: f64, currency: &str) -> String {
+    let symbol = match currency {
+        "USD" => "$",
+        "EUR" => "€",
+        "GBP" => "£",
+        "JPY" => "¥",
+        _ => return format!("{:.2} {}", amount, currency),
+    };
+    format!("{}{:.2}", symbol, amount)
+}
+
+/*
+   format_local Function:
+   - Shows a stored UTC timestamp in the local timezone (storage stays UTC).
+   - `display_format` is config.date_display_format; every list and report showing a date
+     goes through here, so they all follow the setting.
+*/
+fn format_local(timestamp: DateTime<Utc>, display_format: &str) -> String {
+    timestamp
+        .with_timezone(&Local)
+        .format(display_format)
+        .to_string()
+}
+
+/*
+   print_expense_line Function:
+   - Prints one expense as "#N Category (description) - local time - amount 🏷️ tags [id: ID]",
+     with the amount in the expense's own currency (income shows as a green "+amount").
+     The 🏷️ part lists the tags followed by the labels.
+   - The description and tags parts are left out when the expense has none.
+   - Shared by every list so they all look the same and show the same numbers.
+*/
+fn print_expense_line(number: usize, expense: &Expense, display_format: &str) {
+    let description = if expense.description.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", expense.description)
+    };
+    let amount = if expense.is_income() {
+        format!("+{}", format_money(expense.amount, &expense.currency))
+            .green()
+            .to_string()
+    } else {
+        format_money(expense.amount, &expense.currency)
+    };
+    let all_tags: Vec<&str> = expense
+        .tags
+        .iter()
+        .chain(&expense.labels)
+        .map(String::as_str)
+        .collect();
+    let tags = if all_tags.is_empty() {
+        String::new()
+    } else {
+        format!(" 🏷️ {}", all_tags.join(", ")).blue().to_string()
+    };
+    println!(
+        "{} {}{} - {} - {}{} {}",
+        format!("#{}", number).cyan(),
+        expense.category.green(),
+        description,
+        format_local(expense.timestamp, display_format).purple(),
+        amount,
+        tags,
+        format!("[id: {}]", expense.id).dimmed()
+    );
+}
+
+/*
+   over_budget_categories Function:
+   - Totals the expenses of the given month per category with category_totals.
+   - Keeps only the limit budgets (not savings targets) whose total is above the limit.
+   - Returns (category, spent, budget) tuples sorted by category name.
+*/
+fn over_budget_categories(
+    expenses: &[Expense],
+    budgets: &HashMap<String, f64>,
+    year: i32,
+    month: u32,
+    rounding: Rounding,
+) -> Vec<(String, f64, f64)> {
+    let totals = category_totals(
+        expenses.iter().filter(|e| in_month(e, year, month)),
+        rounding,
+    );
+
+    let mut over: Vec<(String, f64, f64)> = budgets
+        .iter()
+        .filter(|(_, &budget)| BudgetKind::of(budget).0 == BudgetKind::Limit)
+        .filter_map(|(category, &budget)| {
+            let spent = totals.get(category).copied().unwrap_or(0.0);
+            (spent > budget).then(|| (category.clone(), spent, budget))
+        })
+        .collect();
+    over.sort_by(|a, b| a.0.cmp(&b.0));
+    over
+}
+
+/*
+   budget_report Function:
+   - Lists every category with a budget, A-Z, against this month's spending (spent_in):
+     amount spent, the budget, what's left and the share used.
+   - Spending limits are colored with budget_status: red when over, yellow from the warning
+     threshold, green otherwise. Savings targets show how much is still to go, green once met.
+   - Then lists the categories with spending this month but no budget as "unbudgeted",
+     with their spending per currency (format_totals).
+   - Budgets are shown in config.default_currency.
+*/
+fn budget_report(tracker: &ExpenseTracker) {
+    let now = Utc::now();
+    let (year, month) = (now.year(), now.month());
+    let decimals = tracker.config.percent_decimals;
+    let money = |amount: f64| format_money(amount, &tracker.config.default_currency);
+
+    println!("\n📋 Budget vs. Actual for {}/{}:", month, year);
+    println!("-------------------------------------");
+    if tracker.budgets.is_empty() {
+        println!("No budgets set.");
+    }
+
+    let mut budgeted: Vec<(&String, &f64)> = tracker.budgets.iter().collect();
+    budgeted.sort_by(|a, b| a.0.cmp(b.0));
+    for (category, &budget) in budgeted {
+        let spent = tracker.spent_in(category, year, month);
+        let line = match BudgetKind::of(budget) {
+            (BudgetKind::Limit, limit) => {
+                let line = format!(
+                    "{}: {} spent of {}, {} left ({} used)",
+                    category,
+                    money(spent),
+                    money(limit),
+                    money(limit - spent),
+                    format_percent(percent_of(spent, limit), decimals)
+                );
+                match budget_status(spent, limit, tracker.config.warn_threshold) {
+                    BudgetStatus::Over => line.red(),
+                    BudgetStatus::Near => line.yellow(),
+                    BudgetStatus::Under => line.green(),
+                }
+            }
+            (BudgetKind::Savings, target) => {
+                let line = format!(
+                    "{}: {} saved of {} target, {} to go ({} reached)",
+                    category,
+                    money(spent),
+                    money(target),
+                    money((target - spent).max(0.0)),
+                    format_percent(percent_of(spent, target), decimals)
+                );
+                if spent >= target {
+                    line.green()
+                } else {
+                    line.yellow()
+                }
+            }
+        };
+        println!("{}", line);
+    }
+
+    let month_expenses: Vec<&Expense> = tracker
+        .expenses
+        .iter()
+        .filter(|e| in_month(e, year, month))
+        .collect();
+    let totals = category_totals(month_expenses.iter().copied(), tracker.config.rounding);
+    let unbudgeted: Vec<(&str, f64)> = ranked_totals(&totals)
+        .into_iter()
+        .filter(|(category, _)| !tracker.budgets.contains_key(*category))
+        .collect();
+    if !unbudgeted.is_empty() {
+        println!("-------------------------------------");
+        println!("🗂️ Unbudgeted:");
+        for (category, _) in unbudgeted {
+            let entries: Vec<&Expense> = month_expenses
+                .iter()
+                .copied()
+                .filter(|e| e.category == category)
+                .collect();
+            println!(
+                "{}: {} spent",
+                category,
+                format_totals(&entries, tracker.config.rounding)
+            );
+        }
+    }
+    println!("-------------------------------------");
+}
+
+/*
+   view_expenses_with_budgets Function:
+   - Shows the expense list with view_expenses.
+   - Then, if any budgeted category is over its limit this month, appends a short
+     highlighted block listing them. Nothing extra is printed when all budgets are fine.
+*/
+fn view_expenses_with_budgets(tracker: &ExpenseTracker) {
+    view_expenses(
+        &tracker.expenses,
+        tracker.config.page_size,
+        &tracker.config.date_display_format,
+        tracker.config.rounding,
+    );
+
+    let now = Utc::now();
+    let over = over_budget_categories(
+        &tracker.expenses,
+        &tracker.budgets,
+        now.year(),
+        now.month(),
+        tracker.config.rounding,
+    );
+    if over.is_empty() {
+        return;
+    }
+
+    println!("{}", "\n🚨 Over budget this month:".red().bold());
+    for (category, spent, budget) in over {
+        println!(
+            "{}",
+            format!(
+                "   {}: {} spent of {} ({})",
+                category,
+                format_money(spent, &tracker.config.default_currency),
+                format_money(budget, &tracker.config.default_currency),
+                format_percent(percent_of(spent, budget), tracker.config.percent_decimals)
+            )
+            .red()
+        );
+    }
+}
+
+// Formats a percentage (already scaled to 0–100) with the given number of decimals, e.g. "42.5%"
+fn format_percent(value: f64, decimals: usize) -> String {
+    format!("{:.*}%", decimals, value)
+}
+
+// Function to sort expenses
+fn sort_expenses(
+    expenses: &mut [Expense],
+    page_size: usize,
+    display_format: &str,
+    rounding: Rounding,
+) -> PromptResult {
+    if expenses.is_empty() {
+        println!("\n❌ No expenses to sort!");
+        return Ok(());
+    }
+
+    println!("\n📌 Choose sorting option:");
+    println!("1️⃣ By Amount (Low to High)");
+    println!("2️⃣ By Amount (High to Low)");
+    println!("3️⃣ By Category (A-Z)");
+    println!("4️⃣ By Date (Newest First)");
+    println!("5️⃣ By Date (Oldest First)");
+
+    let input: String = Input::new()
+        .with_prompt("Enter your choice")
+        .allow_empty(true)
+        .interact_text()?;
+
+    // Map the user input to a sort order; any other input returns to the menu.
+    let order = match input.trim() {
+        "1" => SortOrder::AmountAsc,
+        "2" => SortOrder::AmountDesc,
+        "3" => SortOrder::Category,
+        "4" => SortOrder::DateNewest,
+        "5" => SortOrder::DateOldest,
+        _ => {
+            println!(" ⚠️ Invalid choice! Returning to menu");
+            return Ok(());
+        }
+    };
+
+    apply_sort(expenses, order);
+    println!("\n✅ Expenses sorted!");
+    view_expenses(expenses, page_size, display_format, rounding);
+    Ok(())
+}
+
+/*
+   apply_sort Function:
+   Sorts the expenses in place according to the given order:
+
+   - AmountAsc: Ascending order by amount.
+                Uses total_cmp to compare f64 values, which can't panic even on NaN.
+   - AmountDesc: Descending order by amount.
+                 Reverses the order by swapping a and b.
+   - Category: Alphabetical order by category, ignoring case ("food" next to "Food").
+   - DateNewest: Descending order by timestamp, the most recent expenses come first.
+   - DateOldest: Ascending order by timestamp, the oldest expenses come first.
+   Ties in amount or category are ordered by timestamp (oldest first), and the sorts are
+   stable, so equal entries always come out in the same order.
+*/
+fn apply_sort(expenses: &mut [Expense], order: SortOrder) {
+    match order {
+        SortOrder::AmountAsc => expenses.sort_by(|a, b| {
+            a.amount
+                .total_cmp(&b.amount)
+                .then(a.timestamp.cmp(&b.timestamp))
+        }),
+        SortOrder::AmountDesc => expenses.sort_by(|a, b| {
+            b.amount
+                .total_cmp(&a.amount)
+                .then(a.timestamp.cmp(&b.timestamp))
+        }),
+        SortOrder::Category => expenses.sort_by(|a, b| {
+            a.category
+                .to_lowercase()
+                .cmp(&b.category.to_lowercase())
+                .then(a.timestamp.cmp(&b.timestamp))
+        }),
+        SortOrder::DateNewest => expenses.sort_by_key(|e| std::cmp::Reverse(e.timestamp)),
+        SortOrder::DateOldest => expenses.sort_by_key(|e| e.timestamp),
+    }
+}
+
+/*
+   filter_expenses Function:
+   - Asks whether to filter by category, date range or amount range and runs the matching filter.
+   - The filters print what they found and hand the subset back, so it can be exported
+     straight away with export_menu.
+*/
+fn filter_expenses(
+    expenses: &[Expense],
+    date_format: &str,
+    display_format: &str,
+    rounding: Rounding,
+) -> PromptResult {
+    let options = ["By category", "By date range", "By amount range"];
+    let choice = Select::new()
+        .with_prompt("📌 Filter expenses")
+        .default(0)
+        .items(&options)
+        .interact()?;
+
+    let subset = match choice {
+        0 => filter_by_category(expenses, display_format, rounding),
+        1 => filter_by_date_range(expenses, date_format, display_format),
+        _ => filter_by_amount(expenses, display_format, rounding),
+    }?;
+
+    if subset.is_empty() {
+        return Ok(());
+    }
+    let export = Confirm::new()
+        .with_prompt(format!("Export these {} expense(s)?", subset.len()))
+        .default(false)
+        .interact()?;
+    if export {
+        export_menu(&subset, rounding)?;
+    }
+    Ok(())
+}
+
+// 1-based number of `expense` in the full list, as shown by view_expenses (0 if it isn't there)
+fn list_number(expenses: &[Expense], expense: &Expense) -> usize {
+    expenses
+        .iter()
+        .position(|e| std::ptr::eq(e, expense))
+        .map_or(0, |i| i + 1)
+}
+
+// Prints a subset of expenses between dividers, keeping their numbers from the full list
+fn print_subset(expenses: &[Expense], subset: &[&Expense], display_format: &str) {
+    println!("-------------------------");
+    for expense in subset {
+        print_expense_line(list_number(expenses, expense), expense, display_format);
+    }
+    println!("-------------------------");
+}
+
+/*
+   filter_by_date_range Function:
+   - Prompts for a start and end date in the configured input format.
+   - A blank start means "from the beginning", a blank end means "until today".
+   - An end date before the start date is reported as an error instead of an empty result.
+   - Prints the expenses select_by_date_range finds, with their list numbers, and returns them.
+*/
+fn filter_by_date_range<'a>(
+    expenses: &'a [Expense],
+    date_format: &str,
+    display_format: &str,
+) -> PromptResult<Vec<&'a Expense>> {
+    let hint = date_format_hint(date_format);
+    let start: String = Input::new()
+        .with_prompt(format!("Start date ({}, blank for the beginning)", hint))
+        .allow_empty(true)
+        .interact_text()?;
+    let end: String = Input::new()
+        .with_prompt(format!("End date ({}, blank for today)", hint))
+        .allow_empty(true)
+        .interact_text()?;
+
+    let (start, end) = match parse_date_range(&start, &end, date_format, Utc::now().date_naive()) {
+        Ok(range) => range,
+        Err(e) => {
+            println!("{}", format!("⚠️ {}", e).red());
+            return Ok(Vec::new());
+        }
+    };
+
+    let matches = select_by_date_range(expenses, start, end);
+    let from = start.map_or("the beginning".to_string(), |d| d.to_string());
+    if matches.is_empty() {
+        println!(
+            "{}",
+            format!("⚠️ No expenses from {} to {}.", from, end).yellow()
+        );
+        return Ok(matches);
+    }
+
+    println!("\n📅 Expenses from {} to {}:", from, end);
+    print_subset(expenses, &matches, display_format);
+    Ok(matches)
+}
+
+// The expenses whose date falls in the inclusive range (see in_date_range), in list order
+fn select_by_date_range(
+    expenses: &[Expense],
+    start: Option<NaiveDate>,
+    end: NaiveDate,
+) -> Vec<&Expense> {
+    expenses
+        .iter()
+        .filter(|e| in_date_range(e.timestamp, start, end))
+        .collect()
+}
+
+/*
+   parse_date_range Function:
+   - Parses the start and end of a date range; blank start gives None (no lower bound)
+     and blank end gives `today`.
+   - Returns an error when either date is malformed or the end is before the start.
+*/
+fn parse_date_range(
+    start: &str,
+    end: &str,
+    format: &str,
+    today: NaiveDate,
+) -> Result<(Option<NaiveDate>, NaiveDate), String> {
+    let start = if start.trim().is_empty() {
+        None
+    } else {
+        Some(parse_input_date(start, format)?)
+    };
+    let end = if end.trim().is_empty() {
+        today
+    } else {
+        parse_input_date(end, format)?
+    };
+
+    match start {
+        Some(start) if end < start => {
+            Err(format!("End date {} is before start date {}.", end, start))
+        }
+        _ => Ok((start, end)),
+    }
+}
+
+// True when the timestamp's date lies between start (if any) and end, both inclusive
+fn in_date_range(timestamp: DateTime<Utc>, start: Option<NaiveDate>, end: NaiveDate) -> bool {
+    let date = timestamp.date_naive();
+    start.is_none_or(|start| date >= start) && date <= end
+}
+
+/*
+   filter_by_amount Function:
+   - Prompts for a minimum and maximum amount; blank means no bound on that side.
+   - Lists the expenses select_by_amount finds, then prints how many matched and what they
+     add up to, per currency, and returns them.
+*/
+fn filter_by_amount<'a>(
+    expenses: &'a [Expense],
+    display_format: &str,
+    rounding: Rounding,
+) -> PromptResult<Vec<&'a Expense>> {
+    let min = prompt_amount_bound("Minimum amount (blank for no minimum)")?;
+    let max = prompt_amount_bound("Maximum amount (blank for no maximum)")?;
+
+    if let (Some(min), Some(max)) = (min, max) {
+        if max < min {
+            println!(
+                "{}",
+                format!("⚠️ Maximum {:.2} is below minimum {:.2}.", max, min).red()
+            );
+            return Ok(Vec::new());
+        }
+    }
+
+    let matches = select_by_amount(expenses, min, max);
+    if matches.is_empty() {
+        println!("{}", "⚠️ No expenses in that amount range.".yellow());
+        return Ok(matches);
+    }
+
+    println!("\n💵 Expenses in range:");
+    print_subset(expenses, &matches, display_format);
+    println!(
+        "{} expense(s), total: {}",
+        matches.len(),
+        format_totals(&matches, rounding).bold()
+    );
+    Ok(matches)
+}
+
+// The expenses (not income) whose amount is in the inclusive range, in list order
+fn select_by_amount(expenses: &[Expense], min: Option<f64>, max: Option<f64>) -> Vec<&Expense> {
+    expenses
+        .iter()
+        .filter(|e| !e.is_income() && in_amount_range(e.amount, min, max))
+        .collect()
+}
+
+// Prompts for an optional amount; blank input gives None
+fn prompt_amount_bound(prompt: &str) -> PromptResult<Option<f64>> {
+    let input: String = Input::new()
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .validate_with(|input: &String| parse_amount_bound(input).map(|_| ()))
+        .interact_text()?;
+    Ok(parse_amount_bound(&input).unwrap())
+}
+
+// Parses an optional amount bound: blank is None, anything else must be a finite number
+fn parse_amount_bound(input: &str) -> Result<Option<f64>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    match input.parse::<f64>() {
+        Ok(amount) if amount.is_finite() => Ok(Some(amount)),
+        _ => Err(format!("'{}' is not a valid amount.", input)),
+    }
+}
+
+// True when the amount lies between min and max (both inclusive); a None bound is open
+fn in_amount_range(amount: f64, min: Option<f64>, max: Option<f64>) -> bool {
+    min.is_none_or(|min| amount >= min) && max.is_none_or(|max| amount <= max)
+}
+
+/*
+   filter_by_category Function:
+   - Prompts for one or more categories separated by commas (e.g. "Food, Transport, Rent").
+   - Entries are trimmed, blank ones are ignored, and matching ignores case.
+   - Exact matching is the default. With fuzzy matching chosen, each entry is replaced by the
+     known categories fuzzy_categories finds for it (e.g. "grocery" finds "Groceries"); the
+     matches are shown and only used once the user confirms.
+   - Prints the expenses select_by_categories finds, grouped by category in the order
+     entered, each group with a subtotal, followed by a grand total. Returns them.
+*/
+fn filter_by_category<'a>(
+    expenses: &'a [Expense],
+    display_format: &str,
+    rounding: Rounding,
+) -> PromptResult<Vec<&'a Expense>> {
+    let input: String = Input::new()
+        .with_prompt("📌 Categories to filter (comma-separated)")
+        .interact_text()?;
+    let mut categories = parse_comma_list(&input);
+
+    if categories.is_empty() {
+        println!("{}", "⚠️ No category entered.".yellow());
+        return Ok(Vec::new());
+    }
+
+    let modes = ["Exact match", "Fuzzy match (tolerates typos)"];
+    let fuzzy = Select::new()
+        .with_prompt("How should categories match?")
+        .default(0)
+        .items(&modes)
+        .interact()?
+        == 1;
+    if fuzzy {
+        let matched = fuzzy_categories(expenses, &categories);
+        if matched.is_empty() {
+            println!("\n ⚠️ No category is close to: {}", categories.join(", "));
+            return Ok(Vec::new());
+        }
+        println!("\n🔤 Matching categories: {}", matched.join(", ").green());
+        let confirmed = Confirm::new()
+            .with_prompt("List expenses in these categories?")
+            .default(true)
+            .interact()?;
+        if !confirmed {
+            println!("👍 Nothing listed.");
+            return Ok(Vec::new());
+        }
+        categories = matched;
+    }
+
+    let found = select_by_categories(expenses, &categories);
+    for category in &categories {
+        let group: Vec<&Expense> = found
+            .iter()
+            .copied()
+            .filter(|e| e.category.eq_ignore_ascii_case(category))
+            .collect();
+
+        if group.is_empty() {
+            println!("\n ⚠️ No expenses found for category: {}", category);
+            continue;
+        }
+
+        println!("\n📌 Expenses in category '{}':", group[0].category);
+        print_subset(expenses, &group, display_format);
+        println!("Subtotal: {}", format_totals(&group, rounding));
+    }
+
+    if !found.is_empty() {
+        println!("\nGrand total: {}", format_totals(&found, rounding).bold());
+    }
+    Ok(found)
+}
+
+/*
+   fuzzy_categories Function:
+   - For each search term, collects the categories in use that contain it or are within
+     FUZZY_MATCH_THRESHOLD of it by normalized Levenshtein similarity (ignoring case).
+   - Matches are listed per term, closest first, each category only once.
+*/
+fn fuzzy_categories(expenses: &[Expense], terms: &[String]) -> Vec<String> {
+    let known = CategoryCompletion::from_expenses(expenses).categories;
+    let mut matched: Vec<String> = Vec::new();
+    for term in terms {
+        let term = term.to_lowercase();
+        let mut scored: Vec<(f64, &String)> = known
+            .iter()
+            .filter_map(|category| {
+                let name = category.to_lowercase();
+                let score = strsim::normalized_levenshtein(&term, &name);
+                (name.contains(&term) || score >= FUZZY_MATCH_THRESHOLD)
+                    .then_some((score, category))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, category) in scored {
+            if !matched.contains(category) {
+                matched.push(category.clone());
+            }
+        }
+    }
+    matched
+}
+
+// The expenses in any of the categories (ignoring case), grouped in the order the categories are given
+fn select_by_categories<'a>(expenses: &'a [Expense], categories: &[String]) -> Vec<&'a Expense> {
+    categories
+        .iter()
+        .flat_map(|category| {
+            expenses
+                .iter()
+                .filter(move |e| e.category.eq_ignore_ascii_case(category))
+        })
+        .collect()
+}
+
+// Splits a comma-separated list (categories, tags), trimming entries and dropping blank or
+// repeated ones (ignoring case)
+fn parse_comma_list(input: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    for entry in input.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        if !entries.iter().any(|c| c.eq_ignore_ascii_case(entry)) {
+            entries.push(entry.to_string());
+        }
+    }
+    entries
+}
+
+// Total spending of the given entries per currency, e.g. "$12.50, €3.00" (income is not added),
+// each amount rounded to cents first like the summary totals
+fn format_totals(expenses: &[&Expense], rounding: Rounding) -> String {
+    group_by_currency(expenses)
+        .iter()
+        .map(|(currency, group)| {
+            let total: f64 = group
+                .iter()
+                .filter(|e| !e.is_income())
+                .map(|e| rounding.round_cents(e.amount))
+                .sum();
+            format_money(total, currency)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/*
+   search_expenses Function:
+   - Prompts for a keyword and lists every expense whose category or description contains it
+     (case-insensitive substring match).
+   - Matches are printed like view_expenses, keeping their list number so they can be edited or
+     deleted afterwards.
+*/
+fn search_expenses(expenses: &[Expense], display_format: &str) -> PromptResult {
+    let keyword: String = Input::new().with_prompt("🔍 Search for").interact_text()?;
+    let keyword = keyword.trim().to_lowercase();
+
+    let matches: Vec<(usize, &Expense)> = expenses
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| {
+            e.category.to_lowercase().contains(&keyword)
+                || e.description.to_lowercase().contains(&keyword)
+        })
+        .collect();
+
+    if matches.is_empty() {
+        println!("{}", format!("🔍 No results for '{}'.", keyword).yellow());
+        return Ok(());
+    }
+
+    println!("\n🔍 {} result(s) for '{}':", matches.len(), keyword);
+    println!("-------------------------");
+    for (i, expense) in matches {
+        print_expense_line(i + 1, expense, display_format);
+    }
+    println!("-------------------------");
+    Ok(())
+}
+
+/*
+   Storage Trait:
+   - load() -> Vec<Expense>: Reads every stored expense, in list order.
+   - save(&[Expense]): Replaces what is stored with the given expenses.
+   - load_backup(path) -> Vec<Expense>: Reads a backup made by backup_data_file; plain JSON
+     unless the backend says otherwise.
+   - seal(bytes) / open(bytes): Turn the contents of a file kept next to the data file
+     (budgets, trash, ...) into what is written to disk and back, so those files are
+     protected the same way as the data. Both pass the bytes through unchanged by default.
+   The tracker holds one as a Box<dyn Storage>, so the rest of the code doesn't care where
+   the data lives: JsonStorage by default, SqliteStorage for `.db`/`.sqlite` files (see
+   storage_for), and MemoryStorage for a tracker that isn't backed by a file.
+*/
+trait Storage {
+    fn load(&self) -> Result<Vec<Expense>, Box<dyn Error>>;
+    fn save(&self, expenses: &[Expense]) -> Result<(), Box<dyn Error>>;
+
+    fn load_backup(&self, backup: &Path) -> Result<Vec<Expense>, Box<dyn Error>> {
+        load_json_expenses(&backup.to_string_lossy())
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(plaintext.to_vec())
+    }
+
+    fn open(&self, contents: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(contents.to_vec())
+    }
+}
+
+// The JSON data file (or stdin/stdout for `-`), read by load_json_expenses and written by save_json_expenses;
+// `backed_up` records that this session's first save already made its backup, so autosave doesn't make one per change
+struct JsonStorage {
+    path: String,
+    backed_up: Cell<bool>,
+}
+
+impl Storage for JsonStorage {
+    fn load(&self) -> Result<Vec<Expense>, Box<dyn Error>> {
+        load_json_expenses(&self.path)
+    }
+
+    fn save(&self, expenses: &[Expense]) -> Result<(), Box<dyn Error>> {
+        save_json_expenses(expenses, &self.path, !self.backed_up.get())?;
+        self.backed_up.set(true);
+        Ok(())
+    }
+}
+
+// Keeps the saved expenses in memory only; load returns whatever was saved last
+#[derive(Default)]
+struct MemoryStorage {
+    expenses: RefCell<Vec<Expense>>,
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> Result<Vec<Expense>, Box<dyn Error>> {
+        Ok(self.expenses.borrow().clone())
+    }
+
+    fn save(&self, expenses: &[Expense]) -> Result<(), Box<dyn Error>> {
+        *self.expenses.borrow_mut() = expenses.to_vec();
+        Ok(())
+    }
+}
+
+// True for data files that should be kept in SQLite rather than JSON
+fn is_sqlite_path(path: &str) -> bool {
+    matches!(
+        Path::new(path).extension().and_then(|e| e.to_str()),
+        Some("db" | "sqlite" | "sqlite3")
+    )
+}
+
+// True when the file starts with ENCRYPTED_MAGIC
+fn is_encrypted_file(path: &str) -> bool {
+    let mut magic = [0u8; ENCRYPTED_MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && magic == ENCRYPTED_MAGIC
+}
+
+// Picks the storage backend for a data file from its extension, its contents and config.encrypt_data
+fn storage_for(path: &str, config: &Config) -> Box<dyn Storage> {
+    if is_sqlite_path(path) {
+        return sqlite_storage(path);
+    }
+    if path != STDIO_PATH && (config.encrypt_data || is_encrypted_file(path)) {
+        return encrypted_storage(path);
+    }
+    Box::new(JsonStorage {
+        path: path.to_string(),
+        backed_up: Cell::new(false),
+    })
+}
+
+/*
+   save_expenses function:
+   - Saves the tracker's expenses through its Storage backend and prints
+     a confirmation message upon successful saving (not in pipeline mode, where stdout
+     carries the data).
+   - A failed save is returned to the caller, which decides whether to stop or let the
+     user try again (see save_before_exit).
+*/
+fn save_expenses(tracker: &ExpenseTracker, path: &str) -> Result<(), Box<dyn Error>> {
+    tracker.storage.save(&tracker.expenses)?;
+    if path != STDIO_PATH {
+        println!("💾 Expenses saved successfully!");
+    }
+    Ok(())
+}
+
+// Saves the budgets, overall monthly budget, recurring templates, trash and exchange rates next to the data file at `path`
+// through `storage`, the one the data file was saved with (the first failure is returned; files after it are left as they were)
+fn save_companions(
+    tracker: &ExpenseTracker,
+    storage: &dyn Storage,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    save_budgets(storage, &tracker.budgets, &companion_path(path, "budgets"))?;
+    save_monthly_budget(
+        storage,
+        tracker.monthly_budget,
+        &companion_path(path, "monthly_budget"),
+    )?;
+    save_recurring(
+        storage,
+        &tracker.recurring,
+        &companion_path(path, "recurring"),
+    )?;
+    save_trash(storage, &tracker.trash, &companion_path(path, "trash"))?;
+    save_rates(storage, &tracker.rates, &companion_path(path, "rates"))?;
+    Ok(())
+}
+
+/*
+   save_now Function:
+   - Saves everything (expenses, then the files next to them) and goes back to the menu,
+     so the session so far is safe even if the program is closed without "Save & Exit".
+   - A failed save (of the data file or one of the files next to it) is reported and the
+     changes stay marked as unsaved; the data stays in memory, so the user can fix the
+     problem and save again.
+*/
+fn save_now(tracker: &mut ExpenseTracker, data_path: &str) {
+    match save_expenses(tracker, data_path)
+        .and_then(|()| save_companions(tracker, tracker.storage.as_ref(), data_path))
+    {
+        Ok(()) => {
+            tracker.unsaved_changes = false;
+        }
+        Err(e) => eprintln!(
+            "{}",
+            format!("⚠️ Failed to save {}: {}", data_path, e).red()
+        ),
+    }
+}
+
+/*
+   autosave Function:
+   - Runs after a menu action that left unsaved changes, when config.autosave is on.
+   - Saves like save_now, through the same Storage backend (write_atomic for JSON files, one
+     transaction for SQLite), so saving after every change can't leave a half-written file.
+   - Only a short note is printed on success; a failure keeps the changes marked as unsaved,
+     so the next action tries again, and "Save" or "Save & Exit" can still be used.
+*/
+fn autosave(tracker: &mut ExpenseTracker, data_path: &str) {
+    let saved = tracker
+        .storage
+        .save(&tracker.expenses)
+        .and_then(|()| save_companions(tracker, tracker.storage.as_ref(), data_path));
+    match saved {
+        Ok(()) => {
+            tracker.unsaved_changes = false;
+            println!("{}", "💾 Auto-saved.".dimmed());
+        }
+        Err(e) => eprintln!(
+            "{}",
+            format!(
+                "⚠️ Auto-save to {} failed: {}. Your changes are only in memory for now.",
+                data_path, e
+            )
+            .red()
+        ),
+    }
+}
+
+/*
+   ExitSave Enum:
+   - Saved: The expenses and the files next to them were saved, to the data file or another one
+     the user picked.
+   - Discard: The user chose to quit without saving.
+   - Stay: The user went back to the menu instead of quitting.
+   Returned by save_before_exit.
+*/
+enum ExitSave {
+    Saved,
+    Discard,
+    Stay,
+}
+
+/*
+   save_before_exit Function:
+   - Saves the expenses with save_expenses, then the files next to them with save_companions,
+     when the user picks "Save & Exit".
+   - If that fails (e.g. a read-only folder or a full disk), shows the error and asks what
+     to do: try again, save to a different file (any kind storage_for understands), go back
+     to the menu, or quit without saving. A failed save never ends the program by itself.
+   - Cancelling the question counts as going back to the menu, so nothing is lost.
+*/
+fn save_before_exit(tracker: &ExpenseTracker, data_path: &str) -> ExitSave {
+    let save_all = |path: &str| {
+        save_expenses(tracker, path)
+            .and_then(|()| save_companions(tracker, tracker.storage.as_ref(), path))
+    };
+    let mut path = data_path.to_string();
+    let mut result = save_all(&path);
+    loop {
+        let Err(e) = result else {
+            return ExitSave::Saved;
+        };
+        eprintln!("{}", format!("⚠️ Failed to save {}: {}", path, e).red());
+
+        let options = vec![
+            "🔁 Try again",
+            "📄 Save to a different file",
+            "↩️ Back to the menu",
+            "🚪 Exit without saving",
+        ];
+        let Ok(choice) = Select::new()
+            .with_prompt("Your expenses are not saved yet. What now?")
+            .default(0)
+            .items(&options)
+            .interact()
+        else {
+            return ExitSave::Stay;
+        };
+        result = match choice {
+            0 => save_all(&path),
+            1 => {
+                let Ok(other) = Input::<String>::new()
+                    .with_prompt("File to save the expenses to")
+                    .interact_text()
+                else {
+                    return ExitSave::Stay;
+                };
+                path = other.trim().to_string();
+                let storage = storage_for(&path, &tracker.config);
+                storage
+                    .save(&tracker.expenses)
+                    .and_then(|()| save_companions(tracker, storage.as_ref(), &path))
+                    .inspect(|()| println!("💾 Expenses saved to {}.", path))
+            }
+            2 => return ExitSave::Stay,
+            _ => return ExitSave::Discard,
+        };
+    }
+}
+
+/*
+   save_json_expenses Function:
+   - Serializes the 'expenses' vector into a pretty-formatted JSON string using serde_json.
+   - When the path is `-`, prints the JSON to stdout (pipeline mode) and stops there.
+   - Otherwise creates any missing parent directories and, with `backup` set, backs up the
+     current file with backup_data_file (a failed backup is reported but doesn't stop the
+     save). JsonStorage sets it only for the first save of a session.
+   - Then writes the JSON through
+     write_atomic, so a crash mid-save leaves the previous file intact instead of a
+     half-written one.
+*/
+fn save_json_expenses(
+    expenses: &[Expense],
+    path: &str,
+    backup: bool,
+) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(expenses)?;
+    if path == STDIO_PATH {
+        // Ignore write errors here: a closed pipe (e.g. `| head`) isn't worth a panic.
+        let _ = writeln!(io::stdout(), "{}", json);
+        return Ok(());
+    }
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if backup {
+        if let Err(e) = backup_data_file(Path::new(path), MAX_BACKUPS) {
+            println!("⚠️ Could not back up {}: {}", path, e);
+        }
+    }
+    write_atomic(Path::new(path), json.as_bytes())?;
+    Ok(())
+}
+
+/*
+   SqliteStorage Struct:
+   - path (String): The SQLite database file.
+   - Keeps the expenses in an `expenses` table with one column per Expense field, plus a
+     `position` column holding the list order. split, tags and labels are stored as JSON
+     text, timestamps as RFC 3339. Databases from before the `note` or `tags` column get it
+     added on open.
+   - save rewrites the table inside one transaction, so an interrupted save leaves the
+     previous contents in place.
+   - The first time an empty database is loaded, the expenses of the JSON file with the
+     same name (e.g. expenses.json next to expenses.db) are copied in. `PRAGMA user_version`
+     records that this happened, so emptying the database later doesn't bring them back.
+   Only available in builds with the `sqlite` feature.
+*/
+#[cfg(feature = "sqlite")]
+struct SqliteStorage {
+    path: String,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    const SCHEMA_VERSION: i32 = 2;
+
+    fn open(&self) -> rusqlite::Result<rusqlite::Connection> {
+        let conn = rusqlite::Connection::open(&self.path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS expenses (
+                position INTEGER PRIMARY KEY,
+                id INTEGER NOT NULL,
+                amount REAL NOT NULL,
+                currency TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                category TEXT NOT NULL,
+                description TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                split TEXT NOT NULL,
+                payment_method TEXT NOT NULL,
+                labels TEXT NOT NULL,
+                note TEXT NOT NULL DEFAULT '',
+                tags TEXT NOT NULL DEFAULT '[]'
+            );",
+        )?;
+        // Databases written before notes existed lack the column; add it empty.
+        if conn.prepare("SELECT note FROM expenses LIMIT 0").is_err() {
+            conn.execute_batch("ALTER TABLE expenses ADD COLUMN note TEXT NOT NULL DEFAULT '';")?;
+        }
+        // Likewise for tags, kept apart from labels since they were split.
+        if conn.prepare("SELECT tags FROM expenses LIMIT 0").is_err() {
+            conn.execute_batch("ALTER TABLE expenses ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';")?;
+        }
+        Ok(conn)
+    }
+
+    // Copies the same-named JSON file into a database that has never been written to
+    fn migrate_json(&self, conn: &rusqlite::Connection) -> Result<Vec<Expense>, Box<dyn Error>> {
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let json_path = Path::new(&self.path).with_extension("json");
+        if version != 0 || !json_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let expenses = load_json_expenses(&json_path.to_string_lossy())?;
+        self.save(&expenses)?;
+        println!(
+            "📥 Copied {} expense(s) from {} into {}.",
+            expenses.len(),
+            json_path.display(),
+            self.path
+        );
+        Ok(expenses)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Storage for SqliteStorage {
+    fn load(&self) -> Result<Vec<Expense>, Box<dyn Error>> {
+        let conn = self.open()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, amount, currency, kind, category, description, timestamp, split,
+                    payment_method, labels, note, tags
+             FROM expenses ORDER BY position",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, String>(9)?,
+                row.get::<_, String>(10)?,
+                row.get::<_, String>(11)?,
+            ))
+        })?;
+
+        let mut expenses = Vec::new();
+        for row in rows {
+            let (
+                id,
+                amount,
+                currency,
+                kind,
+                category,
+                description,
+                timestamp,
+                split,
+                method,
+                labels,
+                note,
+                tags,
+            ) = row?;
+            expenses.push(Expense {
+                id: id as u64,
+                amount,
+                currency,
+                kind: if kind == "income" {
+                    EntryKind::Income
+                } else {
+                    EntryKind::Expense
+                },
+                category,
+                description,
+                timestamp: DateTime::parse_from_rfc3339(&timestamp)?.with_timezone(&Utc),
+                split: serde_json::from_str(&split)?,
+                payment_method: method,
+                tags: serde_json::from_str(&tags)?,
+                labels: serde_json::from_str(&labels)?,
+                note,
+            });
+        }
+
+        if expenses.is_empty() {
+            return self.migrate_json(&conn);
+        }
+        Ok(expenses)
+    }
+
+    fn save(&self, expenses: &[Expense]) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.open()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM expenses", [])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO expenses (position, id, amount, currency, kind, category,
+                     description, timestamp, split, payment_method, labels, note, tags)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            for (position, e) in expenses.iter().enumerate() {
+                let kind = if e.is_income() { "income" } else { "expense" };
+                stmt.execute(rusqlite::params![
+                    position as i64,
+                    e.id as i64,
+                    e.amount,
+                    e.currency,
+                    kind,
+                    e.category,
+                    e.description,
+                    e.timestamp.to_rfc3339(),
+                    serde_json::to_string(&e.split)?,
+                    e.payment_method,
+                    serde_json::to_string(&e.labels)?,
+                    e.note,
+                    serde_json::to_string(&e.tags)?,
+                ])?;
+            }
+        }
+        tx.pragma_update(None, "user_version", Self::SCHEMA_VERSION)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/*
+   EncryptedStorage Struct:
+   - path (String): The data file.
+   - passphrase (String): Entered on startup (or taken from PASSPHRASE_ENV).
+   - backed_up (Cell<bool>): Set once this session's first save has made its backup; like
+     JsonStorage, later saves don't make another.
+   - The file is ENCRYPTED_MAGIC, a random 16-byte salt, a random 12-byte nonce and the
+     JSON encrypted with AES-256-GCM. The key is derived from the passphrase and the salt
+     with Argon2, and both salt and nonce are new on every save.
+   - A wrong passphrase (or a damaged file) fails the GCM check, which load reports as an
+     error so load_expenses stops instead of starting fresh.
+   - A plaintext file found while encrypt_data is on is read as JSON and encrypted on the
+     next save. Saves keep the usual backups (encrypted too) and go through write_atomic.
+   - seal and open use the same format for the files next to the data file, so the budgets,
+     trash, recurring bills and rates are encrypted too (plaintext ones are read as they are
+     and encrypted on the next save).
+   Only available in builds with the `encryption` feature.
+*/
+#[cfg(feature = "encryption")]
+struct EncryptedStorage {
+    path: String,
+    passphrase: String,
+    backed_up: Cell<bool>,
+}
+
+#[cfg(feature = "encryption")]
+impl EncryptedStorage {
+    const SALT_LEN: usize = 16;
+    const NONCE_LEN: usize = 12;
+
+    fn cipher(&self, salt: &[u8]) -> Result<aes_gcm::Aes256Gcm, Box<dyn Error>> {
+        use aes_gcm::KeyInit;
+
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("key derivation failed: {}", e))?;
+        Ok(aes_gcm::Aes256Gcm::new(
+            aes_gcm::Key::<aes_gcm::Aes256Gcm>::from_slice(&key),
+        ))
+    }
+
+    fn decrypt_file(&self, path: &Path) -> Result<Vec<Expense>, Box<dyn Error>> {
+        let data = fs::read(path)?;
+        if !data.starts_with(ENCRYPTED_MAGIC) {
+            println!(
+                "🔒 {} isn't encrypted yet; it will be on the next save.",
+                path.display()
+            );
+            return load_json_expenses(&path.to_string_lossy());
+        }
+        let plaintext = self.open(&data)?;
+
+        let (expenses, warnings) = parse_expenses(std::str::from_utf8(&plaintext)?)?;
+        for warning in warnings {
+            println!("⚠️ {}", warning);
+        }
+        Ok(expenses)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl Storage for EncryptedStorage {
+    fn load(&self) -> Result<Vec<Expense>, Box<dyn Error>> {
+        let path = Path::new(&self.path);
+        if !path.exists() {
+            println!("📂 No previous expenses found. Starting fresh.");
+            return Ok(Vec::new());
+        }
+        self.decrypt_file(path)
+    }
+
+    fn save(&self, expenses: &[Expense]) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(expenses)?;
+        let contents = self.seal(json.as_bytes())?;
+
+        let path = Path::new(&self.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !self.backed_up.get() {
+            if let Err(e) = backup_data_file(path, MAX_BACKUPS) {
+                println!("⚠️ Could not back up {}: {}", self.path, e);
+            }
+        }
+        write_atomic(path, &contents)?;
+        self.backed_up.set(true);
+        Ok(())
+    }
+
+    fn load_backup(&self, backup: &Path) -> Result<Vec<Expense>, Box<dyn Error>> {
+        self.decrypt_file(backup)
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        use aes_gcm::aead::rand_core::RngCore;
+        use aes_gcm::aead::{Aead, AeadCore, OsRng};
+
+        let mut salt = [0u8; Self::SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = aes_gcm::Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher(&salt)?
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "encryption failed")?;
+
+        let mut contents = ENCRYPTED_MAGIC.to_vec();
+        contents.extend_from_slice(&salt);
+        contents.extend_from_slice(&nonce);
+        contents.extend_from_slice(&ciphertext);
+        Ok(contents)
+    }
+
+    fn open(&self, contents: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        use aes_gcm::aead::Aead;
+
+        let Some(sealed) = contents.strip_prefix(ENCRYPTED_MAGIC) else {
+            return Ok(contents.to_vec());
+        };
+        if sealed.len() < Self::SALT_LEN + Self::NONCE_LEN {
+            return Err("decryption failed: the file is truncated".into());
+        }
+        let (salt, rest) = sealed.split_at(Self::SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(Self::NONCE_LEN);
+        let plaintext = self
+            .cipher(salt)?
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "decryption failed: wrong passphrase or damaged file")?;
+        Ok(plaintext)
+    }
+}
+
+/*
+   encrypted_storage Function:
+   - Gets the passphrase from PASSPHRASE_ENV, or else asks for it (twice when the file
+     isn't encrypted yet, so a typo doesn't lock the data away).
+   - Without a passphrase (prompt cancelled) the program stops, since the data can't be read.
+*/
+#[cfg(feature = "encryption")]
+fn encrypted_storage(path: &str) -> Box<dyn Storage> {
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let mut prompt =
+                dialoguer::Password::new().with_prompt(format!("🔒 Passphrase for {}", path));
+            if !is_encrypted_file(path) {
+                prompt = prompt
+                    .with_confirmation("Repeat the passphrase", "The passphrases don't match.");
+            }
+            prompt.interact().unwrap_or_else(|_| {
+                eprintln!("⚠️ No passphrase entered; can't open {}.", path);
+                std::process::exit(1);
+            })
+        }
+    };
+    Box::new(EncryptedStorage {
+        path: path.to_string(),
+        passphrase,
+        backed_up: Cell::new(false),
+    })
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encrypted_storage(path: &str) -> Box<dyn Storage> {
+    eprintln!(
+        "⚠️ {} is (or is set to be) encrypted, but encryption isn't enabled in this build. Rebuild with `cargo build --release --features encryption`.",
+        path
+    );
+    std::process::exit(1);
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_storage(path: &str) -> Box<dyn Storage> {
+    Box::new(SqliteStorage {
+        path: path.to_string(),
+    })
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_storage(path: &str) -> Box<dyn Storage> {
+    eprintln!(
+        "⚠️ {} is an SQLite data file, but SQLite support isn't enabled in this build. Rebuild with `cargo build --release --features sqlite`.",
+        path
+    );
+    std::process::exit(1);
+}
+
+/*
+   backup_data_file Function:
+   - Copies the existing data file to a timestamped backup next to it,
+     e.g. `expenses.json.bak-20240501-093000` (UTC), before it gets overwritten.
+   - Keeps only the newest `keep` backups and deletes the older ones.
+   - Does nothing when there is no data file yet.
+*/
+fn backup_data_file(path: &Path, keep: usize) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut backup_name = path.as_os_str().to_owned();
+    backup_name.push(format!(".bak-{}", Utc::now().format("%Y%m%d-%H%M%S")));
+    fs::copy(path, PathBuf::from(backup_name))?;
+
+    let backups = list_backups(path)?;
+    let excess = backups.len().saturating_sub(keep);
+    for old in &backups[..excess] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+// Renames a file that failed to parse to `<name>.corrupt-YYYYMMDD-HHMMSS` and returns the new path
+fn quarantine_file(path: &Path) -> io::Result<PathBuf> {
+    let mut corrupt_name = path.as_os_str().to_owned();
+    corrupt_name.push(format!(".corrupt-{}", Utc::now().format("%Y%m%d-%H%M%S")));
+    let corrupt_path = PathBuf::from(corrupt_name);
+    fs::rename(path, &corrupt_path)?;
+    Ok(corrupt_path)
+}
+
+// Backups of the data file made by backup_data_file, oldest first (the timestamp sorts by name)
+fn list_backups(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}.bak-",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/*
+   restore_backup_menu Function:
+   - Lists the backups of the data file, newest first, and loads the chosen one in place of
+     the current expenses after confirmation.
+   - The replaced list can be brought back with undo; nothing is written until the next save
+     (straight away with autosave).
+   - Restored expenses keep their IDs; the ID counter moves past them if needed.
+*/
+fn restore_backup_menu(tracker: &mut ExpenseTracker, data_path: &str) -> PromptResult {
+    let backups = match list_backups(Path::new(data_path)) {
+        Ok(backups) if !backups.is_empty() => backups,
+        _ => {
+            println!("\n🗄️ No backups found for {}.", data_path);
+            return Ok(());
+        }
+    };
+
+    let names: Vec<String> = backups
+        .iter()
+        .rev()
+        .map(|p| {
+            p.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    let choice = Select::new()
+        .with_prompt("🗄️ Choose a backup to restore")
+        .default(0)
+        .items(&names)
+        .interact()?;
+    let backup = &backups[backups.len() - 1 - choice];
+
+    let confirm = Confirm::new()
+        .with_prompt(format!(
+            "Replace the current {} expense(s) with {}?",
+            tracker.expenses.len(),
+            names[choice]
+        ))
+        .default(false)
+        .interact()?;
+    if !confirm {
+        println!("⚠️ Nothing restored.");
+        return Ok(());
+    }
+
+    let before = tracker.snapshot("restore");
+    tracker.expenses = match tracker.storage.load_backup(backup) {
+        Ok(expenses) => expenses,
+        Err(e) => {
+            tracker.undo = None;
+            println!(
+                "⚠️ Could not read {}: {}. Nothing restored.",
+                names[choice], e
+            );
+            return Ok(());
+        }
+    };
+    let next_id = assign_missing_ids(&mut tracker.expenses, &mut tracker.trash);
+    tracker.next_id = tracker.next_id.max(next_id);
+    tracker.undo = Some(before);
+    tracker.unsaved_changes = true;
+    println!(
+        "✅ Restored {} expense(s) from {}.",
+        tracker.expenses.len(),
+        names[choice]
+    );
+    Ok(())
+}
+
+/*
+   write_atomic Function:
+   - Writes `contents` to a temporary file next to `path` (e.g. `expenses.json.tmp`),
+     flushes it to disk, then renames it over `path`.
+   - The rename replaces the file in one step, so readers see either the old file or the
+     complete new one, never a truncated mix. Until then the original is left untouched.
+*/
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+/*
+   load_expenses Function:
+   - Loads the expenses through the given Storage backend; `path` names it in messages.
+   - If the backend fails (e.g. an unreadable database), the program stops instead of
+     starting empty, so the next save can't overwrite the data.
+*/
+fn load_expenses(storage: &dyn Storage, path: &str) -> Vec<Expense> {
+    match storage.load() {
+        Ok(expenses) => expenses,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!(
+                    "⚠️ Could not load {}: {}. Stopping so nothing is overwritten.",
+                    path, e
+                )
+                .red()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn load_json_expenses(path: &str) -> Result<Vec<Expense>, Box<dyn Error>> {
+    /*
+       Reads the data file at `path` and attempts to deserialize its contents into a vector of expenses.
+       A path of `-` reads the JSON from stdin instead (pipeline mode), reporting problems on stderr.
+
+       Control Flow:
+       - If the file is read successfully (Ok(data)):
+           • Attempts to parse the JSON data with parse_expenses.
+           • Prints a warning for every entry it had to drop.
+           • On parsing error, moves the broken file aside with quarantine_file (so the next
+             save can't overwrite it), tells the user where it went along with the parser's
+             message, and returns an empty vector. If the file can't be moved, returns an
+             error instead, so the caller stops rather than risk overwriting it.
+       - If the file is not found (ErrorKind::NotFound):
+           • Informs the user no previous expenses were found and returns an empty vector.
+       - For any other read error (e.g. no permission), stdin included:
+           • Returns the error; starting empty here would let the next save wipe the file.
+       - stdin that doesn't parse is an error too, since there is no file to move aside:
+         writing an empty list to stdout would wipe the data in `… < data.json > data.json`-style pipelines.
+    */
+    if path == STDIO_PATH {
+        return match io::read_to_string(io::stdin()) {
+            Ok(data) => match parse_expenses(&data) {
+                Ok((expenses, warnings)) => {
+                    for warning in warnings {
+                        eprintln!("⚠️ {}", warning);
+                    }
+                    Ok(expenses)
+                }
+                Err(e) => Err(format!("stdin is not a valid expense list ({})", e).into()),
+            },
+            Err(e) => Err(format!("error reading stdin: {}", e).into()),
+        };
+    }
+
+    match fs::read_to_string(path) {
+        Ok(data) => match parse_expenses(&data) {
+            Ok((expenses, warnings)) => {
+                for warning in warnings {
+                    println!("⚠️ {}", warning);
+                }
+                Ok(expenses)
+            }
+            Err(e) => {
+                println!("{}", format!("⚠️ Could not parse {}: {}", path, e).red());
+                let moved = quarantine_file(Path::new(path)).map_err(|e| {
+                    format!("it is unreadable and could not be moved aside ({})", e)
+                })?;
+                println!(
+                    "📦 Your old data was moved to {}, so nothing is overwritten. Starting fresh.",
+                    moved.display()
+                );
+                Ok(Vec::new())
+            }
+        },
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            println!("📂 No previous expenses found. Starting fresh.");
+            Ok(Vec::new())
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/*
+   parse_expenses Function:
+   - Parses the data file's JSON array one entry at a time, so a single bad entry
+     (e.g. a hand-edited `"amount": "NaN"`) doesn't throw away the whole file.
+   - Entries that don't deserialize, or whose amount isn't a finite number, are dropped.
+   - Returns the good expenses plus one warning message per dropped entry.
+   - Only fails if the text isn't a JSON array at all.
+*/
+fn parse_expenses(data: &str) -> Result<(Vec<Expense>, Vec<String>), serde_json::Error> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(data)?;
+    let mut expenses = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (i, entry) in entries.into_iter().enumerate() {
+        match serde_json::from_value::<Expense>(entry) {
+            Ok(expense) if expense.amount.is_finite() => expenses.push(expense),
+            Ok(expense) => warnings.push(format!(
+                "Skipping entry #{}: amount {} is not a valid number.",
+                i + 1,
+                expense.amount
+            )),
+            Err(e) => warnings.push(format!("Skipping entry #{}: {}.", i + 1, e)),
+        }
+    }
+
+    Ok((expenses, warnings))
+}
+
+/*
+   category_totals Function:
+   - Groups the given expenses by category and sums their amounts.
+   - Income entries are skipped, so the totals are always spending.
+   - For each expense:
+       • Uses .entry() with a cloned category string.
+       • Inserts 0.0 if the category is not present.
+       • Adds the expense amount to the existing total.
+   - Shared by the monthly summary and the month-end reports so both agree.
+*/
+fn category_totals<'a, I>(expenses: I, rounding: Rounding) -> HashMap<String, f64>
+where
+    I: IntoIterator<Item = &'a Expense>,
+{
+    let mut totals: HashMap<String, f64> = HashMap::new();
+    for expense in expenses.into_iter().filter(|e| !e.is_income()) {
+        *totals.entry(expense.category.clone()).or_insert(0.0) +=
+            rounding.round_cents(expense.amount);
+    }
+    totals
+}
+
+// Returns true if the expense happened in the given month of the given year
+fn in_month(expense: &Expense, year: i32, month: u32) -> bool {
+    expense.timestamp.year() == year && expense.timestamp.month() == month
+}
+
+// Returns the (year, month) before the given one, wrapping January back to December
+fn previous_month(year: i32, month: u32) -> (i32, u32) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
+
+/*
+   monthly_summary Function:
+   - Collects the expenses of the current month and year.
+   - Amounts in different currencies can't be added up, so the expenses are grouped per
+     currency and each group gets its own category breakdown, spending total, income total,
+     net cash flow (income - spending) and "your share" line.
+     A heading names the currency when more than one is in use.
+   - Categories are listed from highest to lowest total, each with its percentage of the
+     group's spending.
+   - Each group also gets a horizontal bar chart of the categories, biggest first, where
+     the bar length is the category's share of the group's spending (CHART_WIDTH columns
+     for 100%) followed by that share as a percentage.
+   - When exchange rates are set and the month has non-BASE_CURRENCY expenses, adds a
+     breakdown converted to BASE_CURRENCY. Entries whose currency has no rate are skipped
+     with a warning instead of being counted as 1:1.
+   - With an overall monthly budget set, ends with the budget and how much of it is left.
+*/
+fn monthly_summary(
+    expenses: &[Expense],
+    rates: &StaticRateProvider,
+    config: &Config,
+    monthly_budget: Option<f64>,
+) {
+    let now = Utc::now();
+    for line in monthly_summary_lines(
+        expenses,
+        rates,
+        config,
+        monthly_budget,
+        now.year(),
+        now.month(),
+    ) {
+        println!("{}", line);
+    }
+}
+
+/*
+   monthly_summary_lines Function:
+   - Builds the lines monthly_summary prints for the given month, so the same report can be
+     shown on screen or written to a file by export_summary_menu.
+   - Only the expenses whose timestamp falls in that month and year are counted.
+   - Percentages and rounding follow `config`; the overall monthly budget is shown in
+     config.default_currency.
+*/
+fn monthly_summary_lines(
+    expenses: &[Expense],
+    rates: &StaticRateProvider,
+    config: &Config,
+    monthly_budget: Option<f64>,
+    year: i32,
+    month: u32,
+) -> Vec<String> {
+    let rounding = config.rounding;
+    let month_expenses: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| in_month(e, year, month))
+        .collect();
+
+    if month_expenses.is_empty() {
+        return vec!["\n📂 No expenses recorded for this month.".to_string()];
+    }
+
+    let mut lines = vec![format!("\n📊 Monthly Summary for {}/{}:", month, year)];
+    lines.extend(breakdown_lines(
+        &month_expenses,
+        rates,
+        config.percent_decimals,
+        rounding,
+        "This Month",
+    ));
+
+    if let Some(limit) = monthly_budget {
+        let spent: f64 = month_expenses
+            .iter()
+            .filter(|e| !e.is_income())
+            .map(|e| rounding.round_cents(e.amount))
+            .sum();
+        let left = format_money(limit - spent, &config.default_currency);
+        lines.push(format!(
+            "\n🎯 Overall Monthly Budget: {}, left this month: {}",
+            format_money(limit, &config.default_currency),
+            if spent > limit {
+                left.red()
+            } else {
+                left.green()
+            }
+        ));
+    }
+    lines
+}
+
+/*
+   export_summary_menu Function:
+   - Writes this month's summary (the lines monthly_summary shows, without colors) to a
+     file instead of the individual expenses.
+   - Asks for plain text or Markdown (the report inside a code block under a title, so the
+     chart stays aligned) and for a filename, defaulting to summary_YYYY_MM.txt / .md.
+*/
+fn export_summary_menu(tracker: &ExpenseTracker) -> PromptResult {
+    let formats = vec!["Text", "Markdown"];
+    let markdown = Select::new()
+        .with_prompt("Choose a format for the summary")
+        .default(0)
+        .items(&formats)
+        .interact()?
+        == 1;
+
+    let now = Utc::now();
+    let default_name = format!(
+        "summary_{}_{:02}.{}",
+        now.year(),
+        now.month(),
+        if markdown { "md" } else { "txt" }
+    );
+    let path: String = Input::new()
+        .with_prompt("File to write")
+        .default(default_name)
+        .interact_text()?;
+
+    colored::control::set_override(false);
+    let lines = monthly_summary_lines(
+        &tracker.expenses,
+        &tracker.rates,
+        &tracker.config,
+        tracker.monthly_budget,
+        now.year(),
+        now.month(),
+    );
+    colored::control::unset_override();
+
+    let report = lines.join("\n");
+    let contents = if markdown {
+        format!(
+            "# Monthly Summary {}/{}\n\n```text\n{}\n```\n",
+            now.month(),
+            now.year(),
+            report.trim()
+        )
+    } else {
+        format!("{}\n", report.trim())
+    };
+
+    match fs::write(path.trim(), contents) {
+        Ok(()) => println!("📁 Summary written to `{}`.", path.trim()),
+        Err(e) => println!("⚠️ Failed to write the summary: {}", e),
+    }
+    Ok(())
+}
+
+/*
+   breakdown_lines Function:
+   - The body of the monthly and date range summaries: every line below the heading for
+     the expenses of one period, with `period` finishing the total lines (e.g. "This Month").
+   - Starts each currency's breakdown with the biggest category, its total and share of the
+     spending (ties go to the first alphabetically).
+*/
+fn breakdown_lines(
+    period_expenses: &[&Expense],
+    rates: &StaticRateProvider,
+    percent_decimals: usize,
+    rounding: Rounding,
+    period: &str,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let groups = group_by_currency(period_expenses);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            lines.push(format!("\n💱 {}", currency.bold()));
+        }
+        let category_totals = category_totals(group.iter().copied(), rounding);
+        let total_spent: f64 = category_totals.values().sum();
+
+        lines.push("-------------------------------------".to_string());
+
+        let ranked = ranked_totals(&category_totals);
+        // ranked_totals breaks ties A-Z, so the first entry is the one to call out.
+        if let Some((category, total)) = ranked.first().filter(|_| total_spent > 0.0) {
+            lines.push(
+                format!(
+                    "🥇 Biggest category: {} ({}, {} of spending)",
+                    category,
+                    format_money(*total, currency),
+                    format_percent(percent_of(*total, total_spent), percent_decimals)
+                )
+                .bold()
+                .to_string(),
+            );
+        }
+        for (category, total) in &ranked {
+            lines.push(format!(
+                "Category: {}, Total Spent: {} ({})",
+                category,
+                format_money(*total, currency),
+                format_percent(percent_of(*total, total_spent), percent_decimals)
+            ));
+        }
+
+        let total_income: f64 = group
+            .iter()
+            .filter(|e| e.is_income())
+            .map(|e| rounding.round_cents(e.amount))
+            .sum();
+
+        if total_spent > 0.0 {
+            lines.push("-------------------------------------".to_string());
+            let width = ranked.iter().map(|(c, _)| c.len()).max().unwrap_or(0);
+            for (category, total) in &ranked {
+                let percent = percent_of(*total, total_spent);
+                lines.push(format!(
+                    "{:<width$} {} {}",
+                    category,
+                    share_bar(percent / 100.0, CHART_WIDTH).cyan(),
+                    format_percent(percent, percent_decimals),
+                    width = width
+                ));
+            }
+        }
+
+        lines.push("-------------------------------------".to_string());
+        lines.push(format!(
+            "💰 Total Spending {}: {}",
+            period,
+            format_money(total_spent, currency)
+        ));
+        lines.push(format!(
+            "💵 Total Income {}: {}",
+            period,
+            format_money(total_income, currency)
+        ));
+        let net = total_income - total_spent;
+        let net_line = format!("📈 Net Cash Flow: {}", format_money(net, currency));
+        if net < 0.0 {
+            lines.push(net_line.red().to_string());
+        } else {
+            lines.push(net_line.green().to_string());
+        }
+
+        // Only worth showing when something in the period was actually shared.
+        if group.iter().any(|e| !e.split.is_empty()) {
+            let your_total: f64 = group
+                .iter()
+                .filter(|e| !e.is_income())
+                .map(|e| rounding.round_cents(your_share(e)))
+                .sum();
+            lines.push(format!(
+                "🧾 Your Share {}: {}",
+                period,
+                format_money(your_total, currency)
+            ));
+        }
+    }
+
+    let foreign = period_expenses.iter().any(|e| e.currency != BASE_CURRENCY);
+    if !rates.rates.is_empty() && foreign {
+        lines.extend(converted_summary_lines(
+            period_expenses,
+            rates,
+            rounding,
+            period,
+        ));
+    }
+    lines
+}
+
+/*
+   weekly_summary Function:
+   - Like monthly_summary, but for the current ISO week (Monday to Sunday, UTC dates).
+   - Prints the same breakdown through breakdown_lines; only the date filter differs.
+*/
+fn weekly_summary(
+    expenses: &[Expense],
+    rates: &StaticRateProvider,
+    percent_decimals: usize,
+    rounding: Rounding,
+) {
+    let week = Utc::now().iso_week();
+    let week_expenses: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| e.timestamp.iso_week() == week)
+        .collect();
+
+    if week_expenses.is_empty() {
+        println!("\n📂 No expenses recorded for this week.");
+        return;
+    }
+
+    let monday =
+        NaiveDate::from_isoywd_opt(week.year(), week.week(), chrono::Weekday::Mon).unwrap();
+    let sunday =
+        NaiveDate::from_isoywd_opt(week.year(), week.week(), chrono::Weekday::Sun).unwrap();
+    println!("\n📊 Weekly Summary for {} to {}:", monday, sunday);
+    for line in breakdown_lines(
+        &week_expenses,
+        rates,
+        percent_decimals,
+        rounding,
+        "This Week",
+    ) {
+        println!("{}", line);
+    }
+}
+
+/*
+   summary_for_range Function:
+   - Like monthly_summary, but for the expenses dated from `start` to `end` (both inclusive),
+     e.g. a statement period that doesn't line up with calendar months.
+   - Prints the same breakdown through breakdown_lines.
+*/
+fn summary_for_range(
+    expenses: &[Expense],
+    start: NaiveDate,
+    end: NaiveDate,
+    rates: &StaticRateProvider,
+    percent_decimals: usize,
+    rounding: Rounding,
+) {
+    let period_expenses = select_by_date_range(expenses, Some(start), end);
+    if period_expenses.is_empty() {
+        println!("\n📂 No expenses recorded from {} to {}.", start, end);
+        return;
+    }
+
+    println!("\n📊 Summary from {} to {}:", start, end);
+    for line in breakdown_lines(
+        &period_expenses,
+        rates,
+        percent_decimals,
+        rounding,
+        "This Period",
+    ) {
+        println!("{}", line);
+    }
+}
+
+/*
+   range_summary_menu Function:
+   - Prompts for a start and an end date in the configured input format (blank end for today).
+   - Malformed dates and an end before the start are rejected and asked for again.
+   - Shows summary_for_range for the chosen dates.
+*/
+fn range_summary_menu(tracker: &ExpenseTracker) -> PromptResult {
+    let date_format = &tracker.config.date_input_format;
+    let hint = date_format_hint(date_format);
+
+    let start: String = Input::new()
+        .with_prompt(format!("Start date ({})", hint))
+        .validate_with(|input: &String| parse_input_date(input, date_format).map(|_| ()))
+        .interact_text()?;
+    let start = parse_input_date(&start, date_format).unwrap();
+
+    let end: String = Input::new()
+        .with_prompt(format!("End date ({}, blank for today)", hint))
+        .allow_empty(true)
+        .validate_with(|input: &String| {
+            parse_date_range("", input, date_format, Utc::now().date_naive()).and_then(
+                |(_, end)| {
+                    if end < start {
+                        Err(format!("End date {} is before start date {}.", end, start))
+                    } else {
+                        Ok(())
+                    }
+                },
+            )
+        })
+        .interact_text()?;
+    let (_, end) = parse_date_range("", &end, date_format, Utc::now().date_naive()).unwrap();
+
+    summary_for_range(
+        &tracker.expenses,
+        start,
+        end,
+        &tracker.rates,
+        tracker.config.percent_decimals,
+        tracker.config.rounding,
+    );
+    Ok(())
+}
+
+/*
+   inflation_report_menu Function:
+   - Needs at least one year in config.inflation_index (set in Settings).
+   - Asks for the reference year, offering the current year if it has an index and the
+     latest indexed year otherwise; only years with an index are accepted.
+   - Prints inflation_report for every expense.
+*/
+fn inflation_report_menu(tracker: &ExpenseTracker) -> PromptResult {
+    let index = &tracker.config.inflation_index;
+    let Some(&latest) = index.keys().next_back() else {
+        println!("\n📈 No inflation index set yet. Add one per year in Settings first.");
+        return Ok(());
+    };
+    if !tracker.expenses.iter().any(|e| !e.is_income()) {
+        println!("\n❌ No expenses recorded yet.");
+        return Ok(());
+    }
+
+    let this_year = Utc::now().year();
+    let default_year = if index.contains_key(&this_year) {
+        this_year
+    } else {
+        latest
+    };
+    let reference_year: i32 = Input::new()
+        .with_prompt("Show totals in which year's prices?")
+        .default(default_year)
+        .validate_with(|year: &i32| {
+            if index.contains_key(year) {
+                Ok(())
+            } else {
+                Err(format!("{} has no inflation index.", year))
+            }
+        })
+        .interact_text()?;
+
+    inflation_report(
+        &tracker.expenses,
+        index,
+        reference_year,
+        tracker.config.rounding,
+        &tracker.config.date_display_format,
+    );
+    Ok(())
+}
+
+// `amount` spent in `year` restated in `reference_year` prices; None when either year has no usable index
+fn adjust_for_inflation(
+    amount: f64,
+    year: i32,
+    reference_year: i32,
+    index: &BTreeMap<i32, f64>,
+) -> Option<f64> {
+    let from = index.get(&year).filter(|value| **value > 0.0)?;
+    let to = index.get(&reference_year)?;
+    Some(amount * to / from)
+}
+
+/*
+   inflation_report Function:
+   - Totals the spending (income left out) per year and currency twice: nominal, as spent,
+     and adjusted to `reference_year` prices with adjust_for_inflation.
+   - An expense from a year without an index is counted at its nominal amount in both
+     columns, with a warning naming that entry.
+   - Ends with the overall nominal and adjusted totals per currency.
+*/
+fn inflation_report(
+    expenses: &[Expense],
+    index: &BTreeMap<i32, f64>,
+    reference_year: i32,
+    rounding: Rounding,
+    display_format: &str,
+) {
+    let spending: Vec<&Expense> = expenses.iter().filter(|e| !e.is_income()).collect();
+    println!(
+        "\n📈 Nominal vs. Inflation-Adjusted Spending ({} prices):",
+        reference_year
+    );
+
+    for expense in &spending {
+        if adjust_for_inflation(
+            expense.amount,
+            expense.timestamp.year(),
+            reference_year,
+            index,
+        )
+        .is_none()
+        {
+            println!(
+                "{}",
+                format!(
+                    "⚠️ No inflation index for {}: #{} {} ({}, {}) is counted at its nominal amount.",
+                    expense.timestamp.year(),
+                    list_number(expenses, expense),
+                    expense.category,
+                    format_money(expense.amount, &expense.currency),
+                    format_local(expense.timestamp, display_format)
+                )
+                .yellow()
+            );
+        }
+    }
+
+    let groups = group_by_currency(&spending);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+
+        let mut years: BTreeMap<i32, (f64, f64)> = BTreeMap::new();
+        for expense in group {
+            let year = expense.timestamp.year();
+            let nominal = rounding.round_cents(expense.amount);
+            let adjusted = adjust_for_inflation(expense.amount, year, reference_year, index)
+                .map_or(nominal, |amount| rounding.round_cents(amount));
+            let totals = years.entry(year).or_insert((0.0, 0.0));
+            totals.0 += nominal;
+            totals.1 += adjusted;
+        }
+
+        println!("-------------------------------------");
+        for (year, (nominal, adjusted)) in &years {
+            println!(
+                "{}: Nominal {}, Adjusted {}",
+                year,
+                format_money(*nominal, currency),
+                format_money(*adjusted, currency)
+            );
+        }
+        println!("-------------------------------------");
+        let nominal: f64 = years.values().map(|(nominal, _)| nominal).sum();
+        let adjusted: f64 = years.values().map(|(_, adjusted)| adjusted).sum();
+        println!("💰 Nominal Total: {}", format_money(nominal, currency));
+        println!(
+            "💰 Adjusted Total ({} prices): {}",
+            reference_year,
+            format_money(adjusted, currency).bold()
+        );
+    }
+}
+
+/*
+   yearly_summary Function:
+   - Collects the current year's spending (income left out).
+   - Grouped per currency like monthly_summary; for each currency prints:
+       • the total of every month from January to December, with $0.00 for months without
+         expenses so the shape of the year stays visible;
+       • the year's total per category, biggest first;
+       • the grand total for the year.
+*/
+fn yearly_summary(expenses: &[Expense], rounding: Rounding) {
+    let year = Utc::now().year();
+    let year_expenses: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| !e.is_income() && e.timestamp.year() == year)
+        .collect();
+
+    if year_expenses.is_empty() {
+        println!("\n📂 No expenses recorded for {}.", year);
+        return;
+    }
+
+    println!("\n🗓️ Yearly Summary for {}:", year);
+
+    let groups = group_by_currency(&year_expenses);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+
+        let mut month_totals = [0.0; 12];
+        for expense in group {
+            month_totals[expense.timestamp.month0() as usize] +=
+                rounding.round_cents(expense.amount);
+        }
+
+        println!("-------------------------------------");
+        for (month, total) in (1..=12).zip(month_totals) {
+            let name = NaiveDate::from_ymd_opt(year, month, 1)
+                .unwrap()
+                .format("%b");
+            println!("{}: {}", name, format_money(total, currency));
+        }
+
+        println!("-------------------------------------");
+        let category_totals = category_totals(group.iter().copied(), rounding);
+        for (category, total) in ranked_totals(&category_totals) {
+            println!(
+                "Category: {}, Total Spent: {}",
+                category,
+                format_money(total, currency)
+            );
+        }
+
+        println!("-------------------------------------");
+        println!(
+            "💰 Total Spending in {}: {}",
+            year,
+            format_money(month_totals.iter().sum(), currency)
+        );
+    }
+}
+
+/*
+   payment_method_summary Function:
+   - Totals the current month's spending (income left out) per payment method, biggest first.
+   - Grouped per currency like monthly_summary, with a heading when more than one is in use.
+*/
+fn payment_method_summary(expenses: &[Expense], rounding: Rounding) {
+    let now = Utc::now();
+    let month_expenses: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| !e.is_income() && in_month(e, now.year(), now.month()))
+        .collect();
+
+    if month_expenses.is_empty() {
+        println!("\n📂 No expenses recorded for this month.");
+        return;
+    }
+
+    println!(
+        "\n💳 Spending by Payment Method for {}/{}:",
+        now.month(),
+        now.year()
+    );
+    let groups = group_by_currency(&month_expenses);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+        let mut totals: HashMap<String, f64> = HashMap::new();
+        for expense in group {
+            *totals.entry(expense.payment_method.clone()).or_insert(0.0) +=
+                rounding.round_cents(expense.amount);
+        }
+
+        println!("-------------------------------------");
+        for (method, total) in ranked_totals(&totals) {
+            println!(
+                "Payment Method: {}, Total Spent: {}",
+                method,
+                format_money(total, currency)
+            );
+        }
+        println!("-------------------------------------");
+    }
+}
+
+/*
+   month_comparison Function:
+   - Compares this month's spending per category with the previous month's (December of last
+     year when run in January, see previous_month), both totalled with category_totals.
+   - Every category seen in either month gets a line with both totals, the change and the
+     change in percent; a month without the category counts as 0, and a category that is new
+     this month is marked "new" instead of an infinite percentage.
+   - ▲ (red) means more spending than last month, ▼ (green) less.
+   - Grouped per currency like monthly_summary, with a heading when more than one is in use,
+     and ends each group with the change in total spending.
+*/
+fn month_comparison(expenses: &[Expense], percent_decimals: usize, rounding: Rounding) {
+    let now = Utc::now();
+    let (year, month) = (now.year(), now.month());
+    let (prev_year, prev_month) = previous_month(year, month);
+    let both_months: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| {
+            !e.is_income() && (in_month(e, year, month) || in_month(e, prev_year, prev_month))
+        })
+        .collect();
+
+    if both_months.is_empty() {
+        println!("\n📂 No expenses recorded this month or last month.");
+        return;
+    }
+
+    println!(
+        "\n📉 This Month vs. Last Month ({}/{} vs. {}/{}):",
+        month, year, prev_month, prev_year
+    );
+    let groups = group_by_currency(&both_months);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+        let current = category_totals(
+            group.iter().copied().filter(|e| in_month(e, year, month)),
+            rounding,
+        );
+        let previous = category_totals(
+            group
+                .iter()
+                .copied()
+                .filter(|e| in_month(e, prev_year, prev_month)),
+            rounding,
+        );
+        let categories: BTreeSet<&String> = current.keys().chain(previous.keys()).collect();
+
+        println!("-------------------------------------");
+        for category in categories {
+            let now_total = current.get(category).copied().unwrap_or(0.0);
+            let then_total = previous.get(category).copied().unwrap_or(0.0);
+            println!(
+                "{}: {} → {} {}",
+                category,
+                format_money(then_total, currency),
+                format_money(now_total, currency),
+                change_label(then_total, now_total, currency, percent_decimals)
+            );
+        }
+
+        println!("-------------------------------------");
+        let now_total: f64 = current.values().sum();
+        let then_total: f64 = previous.values().sum();
+        println!(
+            "💰 Total: {} → {} {}",
+            format_money(then_total, currency),
+            format_money(now_total, currency),
+            change_label(then_total, now_total, currency, percent_decimals)
+        );
+    }
+}
+
+// "▲ +$12.00 (+25.0%)" in red for more spending, "▼ -$3.00 (-10.0%)" in green for less, "(no change)" otherwise
+fn change_label(previous: f64, current: f64, currency: &str, percent_decimals: usize) -> String {
+    let delta = current - previous;
+    if delta.abs() < 0.005 {
+        return "(no change)".dimmed().to_string();
+    }
+    let percent = if previous == 0.0 {
+        "new".to_string()
+    } else {
+        format!("{:+.*}%", percent_decimals, percent_of(delta, previous))
+    };
+    if delta > 0.0 {
+        format!("▲ +{} ({})", format_money(delta, currency), percent)
+            .red()
+            .to_string()
+    } else {
+        format!("▼ -{} ({})", format_money(-delta, currency), percent)
+            .green()
+            .to_string()
+    }
+}
+
+/*
+   weekday_summary_menu Function:
+   - Asks whether to look at this month's expenses or all of them, then prints
+     weekday_summary for that selection.
+*/
+fn weekday_summary_menu(expenses: &[Expense], rounding: Rounding) -> PromptResult {
+    let periods = vec!["This month", "All time"];
+    let this_month = Select::new()
+        .with_prompt("Which expenses?")
+        .default(0)
+        .items(&periods)
+        .interact()?
+        == 0;
+
+    let now = Utc::now();
+    let selected: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| !this_month || in_month(e, now.year(), now.month()))
+        .collect();
+    let period = if this_month {
+        format!("{}/{}", now.month(), now.year())
+    } else {
+        "All Time".to_string()
+    };
+    weekday_summary(&selected, &period, rounding);
+    Ok(())
+}
+
+/*
+   weekday_summary Function:
+   - Buckets the spending (income left out) by the day of the week it happened on, in the
+     local timezone, Monday to Sunday, with the total and count per day (see weekday_totals).
+   - Each day gets a bar scaled to the busiest day (CHART_WIDTH columns), so a weekend spike
+     stands out even when every day is small.
+   - Grouped per currency like monthly_summary, with a heading when more than one is in use.
+*/
+fn weekday_summary(expenses: &[&Expense], period: &str, rounding: Rounding) {
+    let spending: Vec<&Expense> = expenses
+        .iter()
+        .copied()
+        .filter(|e| !e.is_income())
+        .collect();
+    if spending.is_empty() {
+        println!("\n📂 No expenses recorded for this period.");
+        return;
+    }
+
+    println!("\n📆 Spending by Day of the Week ({}):", period);
+    let groups = group_by_currency(&spending);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+        let days = weekday_totals(group, rounding);
+        let busiest = days.iter().map(|(_, total)| *total).fold(0.0, f64::max);
+
+        println!("-------------------------------------");
+        for (day, (count, total)) in days.iter().enumerate() {
+            let weekday = Weekday::try_from(day as u8).expect("seven weekdays");
+            let share = if busiest > 0.0 { total / busiest } else { 0.0 };
+            println!(
+                "{} {:>12} ({:>3}) {}",
+                weekday,
+                format_money(*total, currency),
+                count,
+                share_bar(share, CHART_WIDTH).cyan()
+            );
+        }
+        println!("-------------------------------------");
+    }
+}
+
+// Count and total of the expenses per local weekday, Monday first
+fn weekday_totals(expenses: &[&Expense], rounding: Rounding) -> [(usize, f64); 7] {
+    let mut days = [(0, 0.0); 7];
+    for expense in expenses {
+        let day = expense.timestamp.with_timezone(&Local).weekday();
+        let entry = &mut days[day.num_days_from_monday() as usize];
+        entry.0 += 1;
+        entry.1 += rounding.round_cents(expense.amount);
+    }
+    days
+}
+
+/*
+   converted_summary_lines Function:
+   - Converts each category's spending to BASE_CURRENCY with convert_total and the
+     rates the user set, and returns the lines listing the converted totals.
+   - Lists the currencies that had no rate, since their entries were left out.
+*/
+fn converted_summary_lines(
+    expenses: &[&Expense],
+    rates: &StaticRateProvider,
+    rounding: Rounding,
+    period: &str,
+) -> Vec<String> {
+    let mut by_category: BTreeMap<&str, Vec<&Expense>> = BTreeMap::new();
+    for expense in expenses.iter().filter(|e| !e.is_income()) {
+        by_category
+            .entry(expense.category.as_str())
+            .or_default()
+            .push(expense);
+    }
+
+    let mut missing: Vec<String> = Vec::new();
+    let mut grand_total = 0.0;
+
+    let mut lines = vec![
+        format!("\n💱 Converted to {}:", BASE_CURRENCY),
+        "-------------------------------------".to_string(),
+    ];
+    for (category, group) in &by_category {
+        let amounts = group.iter().map(|e| (e.amount, e.currency.as_str()));
+        let (total, skipped) = convert_total(amounts, BASE_CURRENCY, rates, rounding);
+        grand_total += total;
+        for currency in skipped {
+            if !missing.contains(&currency) {
+                missing.push(currency);
+            }
+        }
+        lines.push(format!(
+            "Category: {}, Total Spent: {}",
+            category,
+            format_money(total, BASE_CURRENCY)
+        ));
+    }
+    lines.push("-------------------------------------".to_string());
+    lines.push(format!(
+        "💰 Total Spending {}: {}",
+        period,
+        format_money(grand_total, BASE_CURRENCY)
+    ));
+
+    if !missing.is_empty() {
+        lines.push(
+            format!(
+                "⚠️ No exchange rate for {}; those entries were not converted.",
+                missing.join(", ")
+            )
+            .yellow()
+            .to_string(),
+        );
+    }
+    lines
+}
+
+// Category totals sorted from highest to lowest spending (ties A-Z)
+fn ranked_totals(totals: &HashMap<String, f64>) -> Vec<(&str, f64)> {
+    let mut ranked: Vec<(&str, f64)> = totals.iter().map(|(c, t)| (c.as_str(), *t)).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranked
+}
+
+// `part` as a percentage of `whole`; 0.0 when `whole` is zero instead of dividing by it
+fn percent_of(part: f64, whole: f64) -> f64 {
+    if whole == 0.0 {
+        0.0
+    } else {
+        part / whole * 100.0
+    }
+}
+
+// A bar of `█` whose length is `share` (0.0–1.0) of `width` columns, rounded
+fn share_bar(share: f64, width: usize) -> String {
+    "█".repeat((share.clamp(0.0, 1.0) * width as f64).round() as usize)
+}
+
+// Groups expenses by their currency code, A-Z
+fn group_by_currency<'a>(expenses: &[&'a Expense]) -> BTreeMap<String, Vec<&'a Expense>> {
+    let mut groups: BTreeMap<String, Vec<&Expense>> = BTreeMap::new();
+    for expense in expenses {
+        groups
+            .entry(expense.currency.clone())
+            .or_default()
+            .push(expense);
+    }
+    groups
+}
+
+// Summary figures over a set of expense amounts, see expense_stats
+struct ExpenseStats<'a> {
+    count: usize,
+    total: f64,
+    average: f64,
+    median: f64,
+    min: f64,
+    max: f64,
+    largest: &'a Expense,
+    top_category: String,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+    days: i64,
+    per_day: f64,
+    per_week: f64,
+}
+
+/*
+   expense_stats Function:
+   - Computes count, total, average, median, minimum and maximum over the given expenses,
+     with each amount rounded to cents by `rounding` first (like category_totals) and the
+     averages rounded too.
+   - The median works on a sorted copy of the amounts, so the caller's order is untouched.
+   - Also picks the most expensive single expense and the most common category
+     (ties go to the category that comes first A-Z).
+   - Averages per day and per week over the span from the first to the last expense date.
+     The span counts both end days, so a single expense (or one day) is a 1-day span and
+     never a division by zero.
+   - Returns None for an empty list rather than dividing by zero.
+*/
+fn expense_stats<'a>(expenses: &[&'a Expense], rounding: Rounding) -> Option<ExpenseStats<'a>> {
+    let largest = *expenses
+        .iter()
+        .max_by(|a, b| a.amount.total_cmp(&b.amount))?;
+
+    let mut amounts: Vec<f64> = expenses
+        .iter()
+        .map(|e| rounding.round_cents(e.amount))
+        .collect();
+    amounts.sort_by(f64::total_cmp);
+    let count = amounts.len();
+    let total: f64 = amounts.iter().sum();
+    let median = if count.is_multiple_of(2) {
+        rounding.round_cents((amounts[count / 2 - 1] + amounts[count / 2]) / 2.0)
+    } else {
+        amounts[count / 2]
+    };
+
+    let mut category_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for expense in expenses {
+        *category_counts
+            .entry(expense.category.as_str())
+            .or_insert(0) += 1;
+    }
+    let top_category = category_counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, &n)| n)
+        .map(|(category, _)| category.to_string())
+        .unwrap_or_default();
+
+    let first_day = expenses.iter().map(|e| e.timestamp).min()?.date_naive();
+    let last_day = expenses.iter().map(|e| e.timestamp).max()?.date_naive();
+    let days = (last_day - first_day).num_days() + 1;
+    let per_day = total / days as f64;
+
+    Some(ExpenseStats {
+        count,
+        total,
+        average: rounding.round_cents(total / count as f64),
+        median,
+        min: amounts[0],
+        max: amounts[count - 1],
+        largest,
+        top_category,
+        first_day,
+        last_day,
+        days,
+        per_day: rounding.round_cents(per_day),
+        per_week: rounding.round_cents(per_day * 7.0),
+    })
+}
+
+/*
+   top_expenses_menu Function:
+   - Asks how many expenses to show (default 10, at least 1).
+   - Prints the biggest ones from top_expenses, keeping their numbers from the full list,
+     or all of them when there are fewer.
+*/
+fn top_expenses_menu(expenses: &[Expense], display_format: &str) -> PromptResult {
+    if !expenses.iter().any(|e| !e.is_income()) {
+        println!("\n❌ No expenses recorded yet.");
+        return Ok(());
+    }
+
+    let n: usize = Input::new()
+        .with_prompt("How many of the biggest expenses?")
+        .default(10)
+        .validate_with(|n: &usize| {
+            if *n == 0 {
+                Err("Enter at least 1.")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+
+    let top = top_expenses(expenses, n);
+    println!("\n🏆 Top {} expense(s) by amount:", top.len());
+    print_subset(expenses, &top, display_format);
+    Ok(())
+}
+
+// The `n` highest-amount expenses (income left out), biggest first; ties keep list order. The list itself isn't reordered.
+fn top_expenses(expenses: &[Expense], n: usize) -> Vec<&Expense> {
+    let mut sorted: Vec<&Expense> = expenses.iter().filter(|e| !e.is_income()).collect();
+    sorted.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    sorted.truncate(n);
+    sorted
+}
+
+/*
+   recent_activity_menu Function:
+   - Asks how many entries to show (default 5, at least 1).
+   - Prints the latest ones from recent_entries, newest first, with their numbers from the
+     full list so a mistake can be edited or deleted right away.
+*/
+fn recent_activity_menu(expenses: &[Expense], display_format: &str) -> PromptResult {
+    if expenses.is_empty() {
+        println!("\n❌ No expenses recorded yet.");
+        return Ok(());
+    }
+
+    let n: usize = Input::new()
+        .with_prompt("How many recent entries?")
+        .default(5)
+        .validate_with(|n: &usize| {
+            if *n == 0 {
+                Err("Enter at least 1.")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+
+    let recent = recent_entries(expenses, n);
+    println!("\n🕒 Last {} expense(s), newest first:", recent.len());
+    print_subset(expenses, &recent, display_format);
+    Ok(())
+}
+
+// The `n` entries with the latest timestamps, newest first; ties keep list order. The list itself isn't reordered.
+fn recent_entries(expenses: &[Expense], n: usize) -> Vec<&Expense> {
+    let mut sorted: Vec<&Expense> = expenses.iter().collect();
+    sorted.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    sorted.truncate(n);
+    sorted
+}
+
+/*
+   statistics Function:
+   - Prints expense_stats for all recorded expenses (income is left out).
+   - Like monthly_summary, each currency gets its own block, with a heading when more
+     than one is in use.
+*/
+fn statistics(expenses: &[Expense], display_format: &str, rounding: Rounding) {
+    let spending: Vec<&Expense> = expenses.iter().filter(|e| !e.is_income()).collect();
+    if spending.is_empty() {
+        println!("{}", "⚠️ No expenses recorded yet.".yellow());
+        return;
+    }
+
+    println!("\n{}", "📈 Expense Statistics".bold().underline());
+    let groups = group_by_currency(&spending);
+    for (currency, group) in &groups {
+        let Some(stats) = expense_stats(group, rounding) else {
+            continue;
+        };
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+        println!("-------------------------");
+        println!("Count: {}", stats.count);
+        println!("Total: {}", format_money(stats.total, currency));
+        println!("Average: {}", format_money(stats.average, currency));
+        println!("Median: {}", format_money(stats.median, currency));
+        println!("Minimum: {}", format_money(stats.min, currency));
+        println!("Maximum: {}", format_money(stats.max, currency));
+        println!(
+            "Span: {} to {} ({} day(s))",
+            stats.first_day, stats.last_day, stats.days
+        );
+        println!("Average per day: {}", format_money(stats.per_day, currency));
+        println!(
+            "Average per week: {}",
+            format_money(stats.per_week, currency)
+        );
+        println!("Most common category: {}", stats.top_category.green());
+        println!("Most expensive:");
+        print_expense_line(
+            list_number(expenses, stats.largest),
+            stats.largest,
+            display_format,
+        );
+        println!("-------------------------");
+    }
+}
+
+/*
+   budgets_menu Function:
+   - Groups the budget actions: set one with set_budget, list them all with list_budgets,
+     remove one with remove_budget_menu, or set the overall monthly budget with
+     set_monthly_budget, until "Back".
+*/
+fn budgets_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let options = vec![
+        "Set a budget",
+        "List budgets",
+        "Remove a budget",
+        "Set the overall monthly budget",
+        "Back",
+    ];
+    loop {
+        let choice = Select::new()
+            .with_prompt("⚠️ Budgets")
+            .default(0)
+            .items(&options)
+            .interact()?;
+        match choice {
+            0 => set_budget(tracker)?,
+            1 => list_budgets(
+                &tracker.budgets,
+                tracker.monthly_budget,
+                &tracker.config.default_currency,
+            ),
+            2 => remove_budget_menu(tracker)?,
+            3 => set_monthly_budget(tracker)?,
+            _ => return Ok(()),
+        }
+    }
+}
+
+// Prints every budget A-Z in `currency`, marking savings targets as such, then the overall monthly budget if set
+fn list_budgets(budgets: &HashMap<String, f64>, monthly_budget: Option<f64>, currency: &str) {
+    if budgets.is_empty() && monthly_budget.is_none() {
+        println!("\n📂 No budgets set.");
+        return;
+    }
+
+    let mut sorted: Vec<(&String, &f64)> = budgets.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    println!("\n⚠️ Budgets:");
+    println!("-------------------------");
+    for (category, &budget) in sorted {
+        match BudgetKind::of(budget) {
+            (BudgetKind::Limit, limit) => {
+                println!("{}: limit {}", category, format_money(limit, currency))
+            }
+            (BudgetKind::Savings, target) => println!(
+                "{}: savings target {}",
+                category,
+                format_money(target, currency)
+            ),
+        }
+    }
+    if let Some(limit) = monthly_budget {
+        println!(
+            "Overall (all categories): limit {} per month",
+            format_money(limit, currency)
+        );
+    }
+    println!("-------------------------");
+}
+
+// Asks for the overall monthly budget; 0 removes it so only the category budgets are checked
+fn set_monthly_budget(tracker: &mut ExpenseTracker) -> PromptResult {
+    let mut input = Input::new().with_prompt("Overall monthly budget (0 to remove)");
+    if let Some(limit) = tracker.monthly_budget {
+        input = input.default(limit);
+    }
+    let amount: f64 = input
+        .validate_with(|amount: &f64| {
+            if amount.is_finite() && *amount >= 0.0 {
+                Ok(())
+            } else {
+                Err("Enter an amount of 0 or more.")
+            }
+        })
+        .interact_text()?;
+
+    if amount == 0.0 {
+        tracker.monthly_budget = None;
+        tracker.unsaved_changes = true;
+        println!("✅ Overall monthly budget removed.");
+    } else {
+        tracker.monthly_budget = Some(amount);
+        tracker.unsaved_changes = true;
+        println!(
+            "✅ Overall monthly budget set to {}",
+            format_money(amount, &tracker.config.default_currency)
+        );
+    }
+    Ok(())
+}
+
+// Asks for a category and removes its budget; new expenses in it are no longer checked
+fn remove_budget_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let category: String = Input::new()
+        .with_prompt("Category whose budget to remove")
+        .validate_with(|category: &String| validate_category(category))
+        .interact_text()?;
+
+    // Budgets saved before categories were normalized may differ in case, so match loosely.
+    let key = tracker
+        .budgets
+        .keys()
+        .find(|k| k.trim().eq_ignore_ascii_case(category.trim()))
+        .cloned();
+    match key.and_then(|key| tracker.remove_budget(&key).map(|_| key)) {
+        Some(key) => println!("✅ Budget for '{}' removed.", key),
+        None => println!("⚠️ There is no budget for '{}'.", category.trim()),
+    }
+    Ok(())
+}
+
+/*
+   set_budget Function:
+   - Prompts the user to enter a category to set a budget for.
+   - Asks whether it is a spending limit or a savings target.
+   - Prompts the user to input the amount for that category.
+   - Inserts the category and its budget into the tracker’s budgets (a HashMap),
+     storing savings targets as negative values.
+   - Prints a confirmation message showing the budget set.
+*/
+fn set_budget(tracker: &mut ExpenseTracker) -> PromptResult {
+    let category: String = Input::new()
+        .with_prompt("Enter category name to set a budget for")
+        .validate_with(|category: &String| validate_category(category))
+        .interact_text()?;
+    let category = normalize_category(&category);
+
+    let kinds = vec![
+        "Spending limit (warn when spending goes above it)",
+        "Savings target (warn while contributions are below it)",
+    ];
+    let kind = match Select::new()
+        .with_prompt("What kind of budget is it?")
+        .default(0)
+        .items(&kinds)
+        .interact()?
+    {
+        0 => BudgetKind::Limit,
+        _ => BudgetKind::Savings,
+    };
+
+    let budget: f64 = Input::new()
+        .with_prompt(format!("Enter budget amount for '{}'", category))
+        .interact_text()?;
+    let budget = budget.abs();
+    if let Err(e) = tracker.set_budget(&category, kind, budget) {
+        println!("⚠️ {}", e);
+        return Ok(());
+    }
+
+    let budget = format_money(budget, &tracker.config.default_currency);
+    match kind {
+        BudgetKind::Limit => {
+            println!("✅ Budget of {} set for category '{}'", budget, category);
+        }
+        BudgetKind::Savings => {
+            println!(
+                "✅ Savings target of {} set for category '{}'",
+                budget, category
+            );
+        }
+    }
+    Ok(())
+}
+
+// Function to ask for an export format and export the given expenses to its default file
+fn export_menu(expenses: &[&Expense], rounding: Rounding) -> PromptResult {
+    let formats = vec!["CSV", "Markdown", "JSON"];
+    let choice = Select::new()
+        .with_prompt("Choose an export format")
+        .default(0)
+        .items(&formats)
+        .interact()?;
+
+    let result = match choice {
+        0 => {
+            let all_columns = CsvColumn::ALL.map(CsvColumn::header).join(", ");
+            let columns: String = Input::new()
+                .with_prompt("Columns to export, in order")
+                .default(all_columns)
+                .validate_with(|input: &String| parse_csv_columns(input).map(|_| ()))
+                .interact_text()?;
+            let with_totals = Confirm::new()
+                .with_prompt("Add category and grand total rows at the end?")
+                .default(false)
+                .interact()?;
+            export_to_csv(
+                expenses,
+                CSV_FILE,
+                &parse_csv_columns(&columns).unwrap(),
+                with_totals,
+                rounding,
+            )
+        }
+        1 => export_to_markdown(expenses, MARKDOWN_FILE, rounding),
+        _ => {
+            let path: String = Input::new()
+                .with_prompt("File to export to")
+                .default(JSON_EXPORT_FILE.to_string())
+                .interact_text()?;
+            export_to_json(expenses, path.trim())
+        }
+    };
+    if let Err(e) = result {
+        println!("⚠️ Failed to export: {}", e);
+    }
+    Ok(())
+}
+
+/*
+   parse_csv_columns Function:
+   - Turns a comma-separated list of column names (e.g. "Date, Amount, Category") into
+     CSV columns in that order; names ignore case and repeats are dropped.
+   - Fails on an unknown name or an empty list, naming the columns that can be chosen.
+*/
+fn parse_csv_columns(input: &str) -> Result<Vec<CsvColumn>, String> {
+    let choices = || CsvColumn::ALL.map(CsvColumn::header).join(", ");
+    let names = parse_comma_list(input);
+    if names.is_empty() {
+        return Err(format!("Pick at least one column from: {}", choices()));
+    }
+    names
+        .iter()
+        .map(|name| {
+            CsvColumn::from_name(name)
+                .ok_or_else(|| format!("Unknown column '{}'. Choose from: {}", name, choices()))
+        })
+        .collect()
+}
+
+/*
+   export_to_markdown Function:
+   - Writes the expenses to `path` as a GitHub-flavored Markdown table with Category, Amount
+     and Date columns, plus Description when any expense has one.
+   - Amounts have two decimals and a right-aligned column; income is marked with "+".
+   - Dates are local, like in the expense list. `|` in text is escaped so it can't break the table.
+   - Ends with a total row of the spending (per currency when several are used).
+*/
+fn export_to_markdown(
+    expenses: &[&Expense],
+    path: &str,
+    rounding: Rounding,
+) -> Result<(), Box<dyn Error>> {
+    let with_description = expenses.iter().any(|e| !e.description.is_empty());
+    let escape = |text: &str| text.replace('|', "\\|");
+    let mut file = create_output(path)?;
+
+    if with_description {
+        writeln!(file, "| Category | Amount | Date | Description |")?;
+        writeln!(file, "|----------|-------:|------|-------------|")?;
+    } else {
+        writeln!(file, "| Category | Amount | Date |")?;
+        writeln!(file, "|----------|-------:|------|")?;
+    }
+
+    for expense in expenses {
+        let sign = if expense.is_income() { "+" } else { "" };
+        let mut row = format!(
+            "| {} | {}{} | {} |",
+            escape(&expense.category),
+            sign,
+            format_money(expense.amount, &expense.currency),
+            expense.timestamp.with_timezone(&Local).format("%Y-%m-%d")
+        );
+        if with_description {
+            row.push_str(&format!(" {} |", escape(&expense.description)));
+        }
+        writeln!(file, "{}", row)?;
+    }
+
+    let empty_cells = if with_description { " | |" } else { " |" };
+    writeln!(
+        file,
+        "| **Total** | **{}** |{}",
+        format_totals(expenses, rounding),
+        empty_cells
+    )?;
+
+    report_exported(path);
+    Ok(())
+}
+
+/*
+   export_to_json Function:
+   - Writes the given expenses (e.g. a filtered subset) as pretty JSON to `path`, in the same
+     shape as the data file so the export can be loaded with `--file` later.
+   - Separate from save_expenses: it never touches the data file or its backups.
+*/
+fn export_to_json(expenses: &[&Expense], path: &str) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(expenses)?;
+    writeln!(create_output(path)?, "{}", json)?;
+    report_exported(path);
+    Ok(())
+}
+
+// Opens an export destination: the file at `path`, or stdout when it is STDIO_PATH (`-`)
+fn create_output(path: &str) -> io::Result<Box<dyn Write>> {
+    if path == STDIO_PATH {
+        return Ok(Box::new(io::stdout()));
+    }
+    Ok(Box::new(File::create(path)?))
+}
+
+// Confirms an export; for stdout the message goes to stderr so it stays out of the data
+fn report_exported(path: &str) {
+    if path == STDIO_PATH {
+        eprintln!("📁 Expenses exported to stdout.");
+    } else {
+        println!("📁 Expenses exported to `{}` successfully!", path);
+    }
+}
+
+/*
+   export_to_csv Function:
+   - Exports the expenses as CSV to `path`, writing only the given columns in the given
+     order (CsvColumn::ALL for the full set).
+   - With `with_totals`, write_csv_totals appends the summary rows after the line items;
+     without it the file holds only the expenses, so it imports back cleanly. Totals need a
+     column to hold their labels (see totals_label_column); without one nothing is written.
+   - The '?' operator propagates any errors that occur during writing.
+*/
+fn export_to_csv(
+    expenses: &[&Expense],
+    path: &str,
+    columns: &[CsvColumn],
+    with_totals: bool,
+    rounding: Rounding,
+) -> Result<(), Box<dyn Error>> {
+    let label_column = if with_totals {
+        let column = totals_label_column(columns).ok_or(
+            "totals need a column besides Amount and Currency to hold their labels, e.g. Category",
+        )?;
+        Some(column)
+    } else {
+        None
+    };
+    let mut wtr = Writer::from_writer(create_output(path)?);
+
+    // Write CSV headers
+    wtr.write_record(columns.iter().map(|column| column.header()))?;
+
+    for (i, expense) in expenses.iter().enumerate() {
+        wtr.write_record(columns.iter().map(|column| column.value(expense, i + 1)))?;
+    }
+
+    if let Some(label_column) = label_column {
+        write_csv_totals(&mut wtr, expenses, columns, label_column, rounding)?;
+    }
+    wtr.flush()?;
+    report_exported(path);
+    Ok(())
+}
+
+// The column the total rows put their labels in: Category when exported, otherwise the first one that isn't Amount or Currency
+fn totals_label_column(columns: &[CsvColumn]) -> Option<CsvColumn> {
+    if columns.contains(&CsvColumn::Category) {
+        return Some(CsvColumn::Category);
+    }
+    columns
+        .iter()
+        .copied()
+        .find(|column| !matches!(column, CsvColumn::Amount | CsvColumn::Currency))
+}
+
+/*
+   write_csv_totals Function:
+   - Writes a blank separator row, then per currency one "TOTAL: <category>" row for each
+     category (sorted by name) and a "TOTAL" row for the currency's grand total.
+   - The labels go in `label_column`, the totals in Amount and Currency, and the other columns stay empty, so the rows stand
+     out from line items. Like format_totals, only spending is added up.
+*/
+fn write_csv_totals<W: Write>(
+    wtr: &mut Writer<W>,
+    expenses: &[&Expense],
+    columns: &[CsvColumn],
+    label_column: CsvColumn,
+    rounding: Rounding,
+) -> Result<(), Box<dyn Error>> {
+    wtr.write_record(columns.iter().map(|_| ""))?;
+    for (currency, group) in group_by_currency(expenses) {
+        let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+        for expense in group.iter().filter(|e| !e.is_income()) {
+            *totals.entry(&expense.category).or_insert(0.0) += rounding.round_cents(expense.amount);
+        }
+        let grand_total: f64 = totals.values().sum();
+        let rows = totals
+            .iter()
+            .map(|(category, total)| (format!("TOTAL: {}", category), *total))
+            .chain(std::iter::once(("TOTAL".to_string(), grand_total)));
+        for (label, total) in rows {
+            wtr.write_record(columns.iter().map(|&column| match column {
+                CsvColumn::Amount => format!("{:.2}", total),
+                CsvColumn::Currency => currency.clone(),
+                _ if column == label_column => label.clone(),
+                _ => String::new(),
+            }))?;
+        }
+    }
+    Ok(())
+}
+
+/*
+   delete_expenses Function:
+   - Checks if the expenses list is empty; if so, prints a message and exits.
+   - Displays the current list of expenses using view_expenses.
+   - Asks whether to delete by list position or by ID; IDs don't shift after sorting
+     or earlier deletions, so they are the safer choice.
+   - By position:
+       • Reads one or more comma-separated list numbers (e.g. "2,5,7") with a prompt.
+       • parse_delete_list checks them all first; if any is not a valid number, nothing is
+         deleted and the bad entries are reported.
+       • Removes them all with ExpenseTracker::delete_indices, then reports how many were deleted.
+   - By ID: shows the expense with that ID and removes it with ExpenseTracker::delete_by_id
+     once the user confirms.
+   - Removed expenses go to the trash when soft delete is on (see report_deleted).
+*/
+fn delete_expenses(tracker: &mut ExpenseTracker) -> PromptResult {
+    if tracker.expenses.is_empty() {
+        println!("\n❌ No expenses to delete!");
+        return Ok(());
+    }
+
+    println!("\n 🗑️ Delete an Expense:");
+    view_expenses(
+        &tracker.expenses,
+        tracker.config.page_size,
+        &tracker.config.date_display_format,
+        tracker.config.rounding,
+    );
+
+    let modes = vec!["By list number", "By ID"];
+    let by_id = Select::new()
+        .with_prompt("Delete by")
+        .default(0)
+        .items(&modes)
+        .interact()?
+        == 1;
+
+    if by_id {
+        let id: u64 = Input::new()
+            .with_prompt("Enter the ID of the expense to delete")
+            .interact_text()?;
+        let Some(position) = tracker.expenses.iter().position(|e| e.id == id) else {
+            println!("⚠️ No expense with ID {}. No expense deleted.", id);
+            return Ok(());
+        };
+
+        print_expense_line(
+            position + 1,
+            &tracker.expenses[position],
+            &tracker.config.date_display_format,
+        );
+        let confirmed = Confirm::new()
+            .with_prompt("Delete this?")
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            println!("👍 Nothing deleted.");
+            return Ok(());
+        }
+
+        match tracker.delete_by_id(id) {
+            Ok(_) => report_deleted(tracker.config.soft_delete, 1),
+            Err(e) => println!("⚠️ {} No expense deleted.", e),
+        }
+        return Ok(());
+    }
+
+    let index_str: String = Input::new()
+        .with_prompt("Enter the number(s) of the expense(s) to delete, separated by commas")
+        .interact_text()?;
+
+    let positions = match parse_delete_list(&index_str, tracker.expenses.len()) {
+        Ok(positions) => positions,
+        Err(invalid) => {
+            println!(
+                "⚠️ Invalid index(es): {}. No expense deleted.",
+                invalid.join(", ")
+            );
+            return Ok(());
+        }
+    };
+
+    match tracker.delete_indices(&positions) {
+        Ok(removed) => report_deleted(tracker.config.soft_delete, removed.len()),
+        Err(e) => println!("⚠️ {} No expense deleted.", e),
+    }
+    Ok(())
+}
+
+/*
+   parse_delete_list Function:
+   - Parses a comma-separated list of 1-based list numbers against a list of `len` items.
+   - Blank entries are ignored and repeated numbers count once.
+   - Returns the 0-based positions sorted from highest to lowest, or every entry that isn't
+     a valid number (so the caller can refuse the whole deletion). An empty list is an error too.
+*/
+fn parse_delete_list(input: &str, len: usize) -> Result<Vec<usize>, Vec<String>> {
+    let mut positions: Vec<usize> = Vec::new();
+    let mut invalid: Vec<String> = Vec::new();
+
+    for entry in input.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.parse().ok().and_then(|n| list_position(n, len)) {
+            Some(position) => positions.push(position),
+            None => invalid.push(entry.to_string()),
+        }
+    }
+
+    if positions.is_empty() && invalid.is_empty() {
+        invalid.push("(none entered)".to_string());
+    }
+    if !invalid.is_empty() {
+        return Err(invalid);
+    }
+
+    positions.sort_unstable_by(|a, b| b.cmp(a));
+    positions.dedup();
+    Ok(positions)
+}
+
+// Turns a 1-based list number into a 0-based position, or None when it isn't in 1..=len
+fn list_position(number: usize, len: usize) -> Option<usize> {
+    (number >= 1 && number <= len).then(|| number - 1)
+}
+
+/*
+   delete_category_menu Function:
+   - Picks a category in use and removes every expense in it (matched ignoring case) with
+     ExpenseTracker::delete_indices, so it is one undo step and soft delete still applies.
+   - Shows how many expenses that is and asks for confirmation first (default no).
+   - If the category has a budget, asks separately whether to remove it as well.
+*/
+fn delete_category_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let categories = CategoryCompletion::from_expenses(&tracker.expenses).categories;
+    if categories.is_empty() {
+        println!("📂 No categories to delete.");
+        return Ok(());
+    }
+
+    let category = categories[Select::new()
+        .with_prompt("Category to delete all expenses from")
+        .default(0)
+        .items(&categories)
+        .interact()?]
+    .clone();
+    let indices: Vec<usize> = tracker
+        .expenses
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.category.eq_ignore_ascii_case(&category))
+        .map(|(i, _)| i)
+        .collect();
+
+    let confirmed = Confirm::new()
+        .with_prompt(format!(
+            "Delete all {} expense(s) in '{}'?",
+            indices.len(),
+            category
+        ))
+        .default(false)
+        .interact()?;
+    if !confirmed {
+        println!("👍 Nothing deleted.");
+        return Ok(());
+    }
+
+    match tracker.delete_indices(&indices) {
+        Ok(removed) => report_deleted(tracker.config.soft_delete, removed.len()),
+        Err(e) => {
+            println!("⚠️ {}", e);
+            return Ok(());
+        }
+    }
+
+    let budget_key = tracker
+        .budgets
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case(&category))
+        .cloned();
+    if let Some(key) = budget_key {
+        let remove = Confirm::new()
+            .with_prompt(format!("Also remove the budget for '{}'?", key))
+            .default(true)
+            .interact()?;
+        if remove {
+            tracker.remove_budget(&key);
+            println!("✅ Budget for '{}' removed.", key);
+        }
+    }
+    Ok(())
+}
+
+// Tells the user where `count` deleted expenses went: the trash with soft delete, otherwise gone for good
+fn report_deleted(soft_delete: bool, count: usize) {
+    if soft_delete {
+        if count == 1 {
+            println!("♻️ Expense moved to the trash.");
+        } else {
+            println!("♻️ {} expenses moved to the trash.", count);
+        }
+    } else if count == 1 {
+        println!("✅ Expense deleted successfully!");
+    } else {
+        println!("✅ {} expenses deleted successfully!", count);
+    }
+}
+
+/*
+   import_from_csv Function:
+   - Reads a CSV file with a header row; the Category, Amount and Date (or Timestamp) columns
+     are required, Description, Currency, Tags, PaymentMethod (or Payment Method), Kind and
+     Note are used when present. Column names ignore case, so a file written by export_to_csv
+     imports back too; Tags are split on CSV_TAG_SEPARATOR.
+   - Kind is "expense" or "income" (any case); an empty or missing Kind is an expense.
+   - Each row becomes a new expense; they are added together with ExpenseTracker::add_all,
+     so one undo takes back the whole import and every row gets a fresh ID. Amounts go through validate_amount,
+     timestamps through parse_csv_timestamp and categories through validate_category and
+     normalize_category, like typed-in ones.
+   - Rows that fail are skipped and described in the returned list instead of aborting the
+     import; a missing required column or an unreadable file is an error.
+   - Returns how many expenses were imported along with the skipped-row messages.
+*/
+fn import_from_csv(
+    path: &str,
+    tracker: &mut ExpenseTracker,
+) -> Result<(usize, Vec<String>), Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let headers = rdr.headers()?.clone();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|h| names.iter().any(|name| h.trim().eq_ignore_ascii_case(name)))
+    };
+    let (Some(category_col), Some(amount_col), Some(timestamp_col)) = (
+        column(&["Category"]),
+        column(&["Amount"]),
+        column(&["Date", "Timestamp"]),
+    ) else {
+        return Err("the file needs Category, Amount and Date columns".into());
+    };
+    let description_col = column(&["Description"]);
+    let currency_col = column(&["Currency"]);
+    let tags_col = column(&["Tags"]);
+    let payment_col = column(&["PaymentMethod", "Payment Method"]);
+    let kind_col = column(&["Kind"]);
+    let note_col = column(&["Note"]);
+
+    let mut imported: Vec<Expense> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+
+    // Row numbers count the header as row 1, so they match what a spreadsheet shows.
+    for (i, record) in rdr.records().enumerate() {
+        let row = i + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                skipped.push(format!("Row {}: {}", row, e));
+                continue;
+            }
+        };
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim();
+
+        let amount_str = field(Some(amount_col));
+        let amount = amount_str.parse::<f64>().ok();
+        let Some(amount) = amount.and_then(|a| validate_amount(a).ok()) else {
+            skipped.push(format!("Row {}: bad amount '{}'", row, amount_str));
+            continue;
+        };
+
+        let timestamp_str = field(Some(timestamp_col));
+        let Some(timestamp) = parse_csv_timestamp(timestamp_str, &tracker.config.date_input_format)
+        else {
+            skipped.push(format!("Row {}: bad date '{}'", row, timestamp_str));
+            continue;
+        };
+
+        let kind_str = field(kind_col);
+        let kind = if kind_str.is_empty() || kind_str.eq_ignore_ascii_case("expense") {
+            EntryKind::Expense
+        } else if kind_str.eq_ignore_ascii_case("income") {
+            EntryKind::Income
+        } else {
+            skipped.push(format!("Row {}: bad kind '{}'", row, kind_str));
+            continue;
+        };
+
+        let category = field(Some(category_col));
+        if let Err(e) = validate_category(category) {
+            skipped.push(format!("Row {}: {}", row, e));
+            continue;
+        }
+        let category = normalize_category(category);
+        let description = field(description_col).to_string();
+        let currency = match field(currency_col) {
+            "" => tracker.config.default_currency.clone(),
+            code => code.to_uppercase(),
+        };
+        let payment_method = match field(payment_col) {
+            "" => default_payment_method(),
+            method => method.to_string(),
+        };
+        let tags = field(tags_col)
+            .split(CSV_TAG_SEPARATOR)
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        imported.push(Expense {
+            id: 0,
+            amount,
+            currency,
+            kind,
+            category,
+            description,
+            timestamp,
+            split: Vec::new(),
+            payment_method,
+            tags,
+            labels: Vec::new(),
+            note: field(note_col).to_string(),
+        });
+    }
+
+    Ok((tracker.add_all("import", imported), skipped))
+}
+
+/*
+   parse_csv_timestamp Function:
+   - Accepts an RFC 3339 timestamp (what export_to_csv writes, e.g. "2024-05-01T09:30:00Z"),
+     the format older exports used ("2024-05-01 09:30:00 UTC"), or a plain date in the configured input format or YYYY-MM-DD.
+   - Plain dates are stamped with date_to_timestamp, like dates typed into add_expense.
+*/
+fn parse_csv_timestamp(input: &str, date_format: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(input) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(timestamp) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S%.f UTC") {
+        return Some(timestamp.and_utc());
+    }
+    [date_format, "%Y-%m-%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+        .map(date_to_timestamp)
+}
+
+// Function to ask for a CSV file, import it and report what was imported and skipped
+fn import_csv_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let path: String = Input::new()
+        .with_prompt("Enter the CSV file to import expenses from")
+        .interact_text()?;
+
+    match import_from_csv(path.trim(), tracker) {
+        Ok((imported, skipped)) => {
+            for reason in &skipped {
+                println!("{}", format!("⚠️ {}", reason).yellow());
+            }
+            println!(
+                "📥 Imported {} expenses, skipped {} bad rows.",
+                imported,
+                skipped.len()
+            );
+        }
+        Err(e) => println!("⚠️ Failed to import: {}", e),
+    }
+    Ok(())
+}
+
+/*
+   load_trash Function:
+   - Reads the soft-deleted expenses from the trash file next to the data file.
+   - A missing file simply means an empty trash; a malformed one is moved aside by
+     read_companion and also starts with an empty trash.
+*/
+fn load_trash(storage: &dyn Storage, path: &Path) -> Result<Vec<Expense>, Box<dyn Error>> {
+    Ok(read_companion(storage, path, "trash")?.unwrap_or_default())
+}
+
+/*
+   save_trash Function:
+   - Writes the trash as pretty JSON next to the data file.
+   - Skips writing when the trash is empty and no trash file exists yet, so users who
+     never soft-delete don't get an extra file.
+*/
+fn save_trash(storage: &dyn Storage, trash: &[Expense], path: &Path) -> Result<(), Box<dyn Error>> {
+    write_companion(storage, trash, trash.is_empty(), path)
+}
+
+/*
+   trash_menu Function:
+   - View: lists the soft-deleted expenses.
+   - Restore: moves the chosen expense back into the expense list.
+   - Empty: permanently removes everything in the trash after confirmation.
+*/
+fn trash_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    if tracker.trash.is_empty() {
+        println!("\n♻️ The trash is empty.");
+        if !tracker.config.soft_delete {
+            println!(
+                "   (Set `soft_delete` to true in config.json to move deleted expenses here.)"
+            );
+        }
+        return Ok(());
+    }
+
+    let choices = vec!["📋 View Trash", "↩️ Restore an Expense", "🔥 Empty Trash"];
+    let selection = Select::new()
+        .with_prompt("♻️ Trash")
+        .default(0)
+        .items(&choices)
+        .interact()?;
+
+    match selection {
+        0 => view_expenses(
+            &tracker.trash,
+            tracker.config.page_size,
+            &tracker.config.date_display_format,
+            tracker.config.rounding,
+        ),
+        1 => {
+            view_expenses(
+                &tracker.trash,
+                tracker.config.page_size,
+                &tracker.config.date_display_format,
+                tracker.config.rounding,
+            );
+            let index: usize = Input::new()
+                .with_prompt("Enter the number of the expense to restore")
+                .interact_text()?;
+            match list_position(index, tracker.trash.len()) {
+                Some(position) => {
+                    tracker.restore_from_trash(position);
+                    println!("✅ Expense restored.");
+                }
+                None => println!("⚠️ Invalid index! Nothing restored."),
+            }
+        }
+        _ => {
+            let confirm = Confirm::new()
+                .with_prompt(format!(
+                    "Permanently delete {} expense(s) in the trash?",
+                    tracker.trash.len()
+                ))
+                .default(false)
+                .interact()?;
+            if confirm {
+                tracker.empty_trash();
+                println!("🔥 Trash emptied.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/*
+   load_config Function:
+   - Reads the config file and deserializes it into a Config.
+   - A missing file is created with the defaults, so they are easy to find and edit.
+   - A malformed file prints a warning (on stderr, so pipeline output stays clean) and also
+     falls back to the defaults; so do out-of-range values of warn_threshold and page_size
+     and a date_display_format that check_display_format rejects.
+*/
+fn load_config(path: &str) -> Config {
+    match fs::read_to_string(path) {
+        Ok(data) => {
+            let mut config: Config = serde_json::from_str(&data).unwrap_or_else(|e| {
+                eprintln!("⚠️ Error parsing {} ({}). Using default settings.", path, e);
+                Config::default()
+            });
+            let defaults = Config::default();
+            if !(config.warn_threshold > 0.0 && config.warn_threshold <= 1.0) {
+                eprintln!(
+                    "⚠️ warn_threshold in {} must be above 0 and at most 1. Using {}.",
+                    path, defaults.warn_threshold
+                );
+                config.warn_threshold = defaults.warn_threshold;
+            }
+            if config.page_size == 0 {
+                eprintln!(
+                    "⚠️ page_size in {} must be at least 1. Using {}.",
+                    path, defaults.page_size
+                );
+                config.page_size = defaults.page_size;
+            }
+            if let Err(e) = check_display_format(&config.date_display_format) {
+                eprintln!(
+                    "⚠️ date_display_format in {}: {} Using \"{}\".",
+                    path, e, defaults.date_display_format
+                );
+                config.date_display_format = defaults.date_display_format;
+            }
+            config
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            let config = Config::default();
+            match save_config(&config, path) {
+                Ok(()) => eprintln!("⚙️ Created {} with the default settings.", path),
+                Err(e) => eprintln!("⚠️ Could not create {}: {}", path, e),
+            }
+            config
+        }
+        Err(_) => {
+            eprintln!("⚠️ Error reading {}. Using default settings.", path);
+            Config::default()
+        }
+    }
+}
+
+// Function to write the config back to disk as pretty JSON
+fn save_config(config: &Config, path: &str) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(config)?;
+    write_atomic(Path::new(path), json.as_bytes())?;
+    Ok(())
+}
+
+/*
+   offer_month_end_report Function:
+   - Works out the previous calendar month (December of last year when run in January).
+   - Does nothing if a report was already offered for that month (tracked in config.last_report_month).
+   - Skips quietly if the previous month has no expenses.
+   - Otherwise asks whether to generate the report; either answer is remembered so the
+     prompt only shows up once, and a "yes" writes the report with write_month_report.
+*/
+fn offer_month_end_report(tracker: &mut ExpenseTracker) -> PromptResult {
+    let now = Utc::now();
+    let (year, month) = previous_month(now.year(), now.month());
+    let key = format!("{:04}-{:02}", year, month);
+
+    if tracker.config.last_report_month.as_deref() == Some(key.as_str()) {
+        return Ok(());
+    }
+
+    let month_expenses: Vec<&Expense> = tracker
+        .expenses
+        .iter()
+        .filter(|e| in_month(e, year, month))
+        .collect();
+
+    if month_expenses.is_empty() {
+        return Ok(());
+    }
+
+    let generate = Confirm::new()
+        .with_prompt(format!(
+            "📅 A new month has started. Save a report for {}?",
+            key
+        ))
+        .default(true)
+        .interact()?;
+
+    if generate {
+        match write_month_report(&month_expenses, year, month, &tracker.config) {
+            Ok(path) => println!("📁 Report saved to `{}`", path.display()),
+            Err(e) => println!("⚠️ Failed to write report: {}", e),
+        }
+    }
+
+    tracker.config.last_report_month = Some(key);
+    if let Err(e) = save_config(&tracker.config, &tracker.config_path) {
+        println!("⚠️ Failed to save settings: {}", e);
+    }
+    Ok(())
+}
+
+/*
+   write_month_report Function:
+   - Creates the configured reports folder if it doesn't exist yet.
+   - Groups the month's expenses by currency, then with category_totals, sorted by category
+     name, so amounts in different currencies are never added together.
+   - Writes either a CSV (`Category,Currency,Total` plus a TOTAL row per currency) or a
+     Markdown table with the amounts formatted by format_money, depending on
+     config.report_format, to `report_YYYY_MM.csv` / `report_YYYY_MM.md`.
+   - Returns the path of the written file.
+*/
+fn write_month_report(
+    expenses: &[&Expense],
+    year: i32,
+    month: u32,
+    config: &Config,
+) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(&config.reports_dir)?;
+
+    let groups: BTreeMap<String, BTreeMap<String, f64>> = group_by_currency(expenses)
+        .into_iter()
+        .map(|(currency, group)| {
+            let totals = category_totals(group.iter().copied(), config.rounding);
+            (currency, totals.into_iter().collect())
+        })
+        .collect();
+
+    let extension = match config.report_format {
+        ReportFormat::Csv => "csv",
+        ReportFormat::Markdown => "md",
+    };
+    let path = Path::new(&config.reports_dir)
+        .join(format!("report_{:04}_{:02}.{}", year, month, extension));
+
+    match config.report_format {
+        ReportFormat::Csv => {
+            let mut wtr = Writer::from_path(&path)?;
+            wtr.write_record(["Category", "Currency", "Total"])?;
+            for (currency, totals) in &groups {
+                for (category, total) in totals {
+                    wtr.write_record([category, currency, &format!("{:.2}", total)])?;
+                }
+                let total_spent: f64 = totals.values().sum();
+                wtr.write_record(["TOTAL", currency, &format!("{:.2}", total_spent)])?;
+            }
+            wtr.flush()?;
+        }
+        ReportFormat::Markdown => {
+            let mut file = File::create(&path)?;
+            writeln!(file, "# Expense Report {:04}-{:02}\n", year, month)?;
+            writeln!(file, "| Category | Total |")?;
+            writeln!(file, "|----------|------:|")?;
+            for (currency, totals) in &groups {
+                for (category, total) in totals {
+                    writeln!(
+                        file,
+                        "| {} | {} |",
+                        category,
+                        format_money(*total, currency)
+                    )?;
+                }
+                let total_spent: f64 = totals.values().sum();
+                writeln!(
+                    file,
+                    "| **Total** | **{}** |",
+                    format_money(total_spent, currency)
+                )?;
+            }
+        }
+    }
+
+    Ok(path)
+}
+
+/*
+   build_split Function:
+   - Turns (person, amount) pairs into SplitShare entries for an expense of `total` in
+     `currency` (used in the error messages).
+   - Rejects blank names, negative shares and shares that don't add up to the total
+     (within half a cent), so a split can never lose or invent money.
+   - Each share is stored as a ratio of the total; any spelling of SELF_NAME ("me", "ME")
+     is stored as SELF_NAME so lookups stay simple.
+*/
+fn build_split(
+    total: f64,
+    shares: &[(String, f64)],
+    currency: &str,
+) -> Result<Vec<SplitShare>, String> {
+    if total <= 0.0 {
+        return Err("Only expenses with a positive amount can be split.".to_string());
+    }
+    if shares.is_empty() {
+        return Err("A split needs at least one person.".to_string());
+    }
+    if shares.iter().any(|(person, _)| person.trim().is_empty()) {
+        return Err("Person names cannot be empty.".to_string());
+    }
+    if shares.iter().any(|(_, amount)| *amount < 0.0) {
+        return Err("Shares cannot be negative.".to_string());
+    }
+
+    let sum: f64 = shares.iter().map(|(_, amount)| amount).sum();
+    if (sum - total).abs() > 0.005 {
+        return Err(format!(
+            "Shares add up to {} but the expense is {}.",
+            format_money(sum, currency),
+            format_money(total, currency)
+        ));
+    }
+
+    Ok(shares
+        .iter()
+        .map(|(person, amount)| SplitShare {
+            person: if person.trim().eq_ignore_ascii_case(SELF_NAME) {
+                SELF_NAME.to_string()
+            } else {
+                person.trim().to_string()
+            },
+            ratio: amount / total,
+        })
+        .collect())
+}
+
+// Returns the part of an expense that is the user's own: everything if it isn't split
+fn your_share(expense: &Expense) -> f64 {
+    if expense.split.is_empty() {
+        return expense.amount;
+    }
+    expense
+        .split
+        .iter()
+        .filter(|share| share.person.eq_ignore_ascii_case(SELF_NAME))
+        .map(|share| expense.amount * share.ratio)
+        .sum()
+}
+
+/*
+   split_expense Function:
+   - Lets the user pick an expense by list number or ID with pick_expense.
+   - Prompts for the comma-separated participants (SELF_NAME stands for the user).
+   - Splits evenly, or asks for each person's amount when an uneven split is wanted.
+   - Validates the shares with build_split and stores them through ExpenseTracker::edit,
+     so the split can be undone and is autosaved like any other edit.
+*/
+fn split_expense(tracker: &mut ExpenseTracker) -> PromptResult {
+    if tracker.expenses.is_empty() {
+        println!("\n❌ No expenses to split!");
+        return Ok(());
+    }
+
+    view_expenses(
+        &tracker.expenses,
+        tracker.config.page_size,
+        &tracker.config.date_display_format,
+        tracker.config.rounding,
+    );
+    let Some(index) = pick_expense(&tracker.expenses)? else {
+        return Ok(());
+    };
+    let amount = tracker.expenses[index].amount;
+    let currency = tracker.expenses[index].currency.clone();
+
+    let people: String = Input::new()
+        .with_prompt(format!(
+            "Who shares it? (comma-separated, use '{}' for yourself)",
+            SELF_NAME
+        ))
+        .interact_text()?;
+    let people: Vec<String> = people
+        .split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    if people.is_empty() {
+        println!("⚠️ No people entered. Nothing changed.");
+        return Ok(());
+    }
+
+    let evenly = Confirm::new()
+        .with_prompt("Split evenly?")
+        .default(true)
+        .interact()?;
+
+    let shares: Vec<(String, f64)> = if evenly {
+        let each = amount / people.len() as f64;
+        people.into_iter().map(|p| (p, each)).collect()
+    } else {
+        people
+            .into_iter()
+            .map(|p| {
+                let amount: f64 = Input::new()
+                    .with_prompt(format!("Amount for {}", p))
+                    .interact_text()?;
+                Ok((p, amount))
+            })
+            .collect::<PromptResult<_>>()?
+    };
+
+    match build_split(amount, &shares, &currency)
+        .and_then(|split| tracker.edit(index, ExpenseEdit::Split(split)))
+    {
+        Ok(()) => println!("✅ Expense split between {} people.", shares.len()),
+        Err(e) => println!("⚠️ {}", e),
+    }
+    Ok(())
+}
+
+/*
+   shared_summary Function:
+   - Adds up every person's share across all split expenses, each share rounded to cents
+     with `rounding` first.
+   - Prints the user's own share separately, then what each other person owes,
+     sorted by name, and the total owed to the user (assuming the user paid).
+   - Like statistics, each currency gets its own block, with a heading when more than one
+     is in use, so amounts in different currencies are never added together.
+*/
+fn shared_summary(expenses: &[Expense], rounding: Rounding) {
+    let shared: Vec<&Expense> = expenses.iter().filter(|e| !e.split.is_empty()).collect();
+    if shared.is_empty() {
+        println!("\n📂 No split expenses recorded.");
+        return;
+    }
+
+    println!("\n👥 Shared Expenses:");
+    let groups = group_by_currency(&shared);
+    for (currency, group) in &groups {
+        if groups.len() > 1 {
+            println!("\n💱 {}", currency.bold());
+        }
+        let mut owed: HashMap<String, f64> = HashMap::new();
+        let mut shared_total = 0.0;
+        for expense in group {
+            shared_total += rounding.round_cents(expense.amount);
+            for share in &expense.split {
+                *owed.entry(share.person.clone()).or_insert(0.0) +=
+                    rounding.round_cents(expense.amount * share.ratio);
+            }
+        }
+
+        let yours = owed.remove(SELF_NAME).unwrap_or(0.0);
+        let mut people: Vec<(String, f64)> = owed.into_iter().collect();
+        people.sort_by(|a, b| a.0.cmp(&b.0));
+
+        println!("-------------------------------------");
+        println!(
+            "Total Of Shared Expenses: {}",
+            format_money(shared_total, currency)
+        );
+        println!("Your Share: {}", format_money(yours, currency));
+        for (person, amount) in &people {
+            println!(
+                "{} owes: {}",
+                person.green(),
+                format_money(*amount, currency)
+            );
+        }
+        println!("-------------------------------------");
+        println!(
+            "💰 Owed To You: {}",
+            format_money(people.iter().map(|(_, amount)| amount).sum(), currency)
+        );
+    }
+}
+
+/*
+   spending_streak Function:
+   - Counts the consecutive days, walking back from `today`, that satisfy the streak rule.
+   - Logged: days with at least one expense. If nothing is logged yet today the streak is
+     still alive, so counting starts from yesterday instead.
+   - UnderBudget: days whose total spending (income aside, amounts rounded to cents with
+     `rounding`) is within `daily_budget`, stopping at the day of the first recorded expense
+     (there is no history before that). Returns None when no
+     daily budget is set, since the rule can't be evaluated.
+*/
+fn spending_streak(
+    expenses: &[Expense],
+    rule: StreakRule,
+    daily_budget: Option<f64>,
+    rounding: Rounding,
+    today: NaiveDate,
+) -> Option<u32> {
+    let mut streak = 0;
+
+    match rule {
+        StreakRule::Logged => {
+            let logged: HashSet<NaiveDate> =
+                expenses.iter().map(|e| e.timestamp.date_naive()).collect();
+            let mut day = today;
+            if !logged.contains(&day) {
+                day = day.pred_opt()?;
+            }
+            while logged.contains(&day) {
+                streak += 1;
+                day = day.pred_opt()?;
+            }
+        }
+        StreakRule::UnderBudget => {
+            let budget = daily_budget?;
+            let first_day = expenses.iter().map(|e| e.timestamp.date_naive()).min();
+            let Some(first_day) = first_day else {
+                return Some(0);
+            };
+
+            let mut daily_totals: HashMap<NaiveDate, f64> = HashMap::new();
+            for expense in expenses.iter().filter(|e| !e.is_income()) {
+                *daily_totals
+                    .entry(expense.timestamp.date_naive())
+                    .or_insert(0.0) += rounding.round_cents(expense.amount);
+            }
+
+            let mut day = today;
+            while day >= first_day && daily_totals.get(&day).copied().unwrap_or(0.0) <= budget {
+                streak += 1;
+                day = day.pred_opt()?;
+            }
+        }
+    }
+
+    Some(streak)
+}
+
+// Function to print the current spending streak on launch
+fn show_streak(tracker: &ExpenseTracker) {
+    let today = Utc::now().date_naive();
+    let rule = tracker.config.streak_rule;
+
+    match spending_streak(
+        &tracker.expenses,
+        rule,
+        tracker.config.daily_budget,
+        tracker.config.rounding,
+        today,
+    ) {
+        Some(0) => println!("{}", "🔥 No active streak yet. Start one today!".yellow()),
+        Some(days) => {
+            let goal = match rule {
+                StreakRule::Logged => "logging expenses".to_string(),
+                StreakRule::UnderBudget => format!(
+                    "under your daily budget of {}",
+                    format_money(
+                        tracker.config.daily_budget.unwrap_or(0.0),
+                        &tracker.config.default_currency
+                    )
+                ),
+            };
+            println!(
+                "{}",
+                format!("🔥 Current streak: {} day(s) {}!", days, goal)
+                    .green()
+                    .bold()
+            );
+        }
+        None => println!(
+            "{}",
+            "⚠️ Set `daily_budget` in config.json to track an under-budget streak.".yellow()
+        ),
+    }
+}
+
+/*
+   DataFormat Enum:
+   - Csv / Json: File formats supported by the budget exporter and importer.
+   - from_path picks the format from the file extension (`.json` is JSON, anything else CSV).
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DataFormat {
+    Csv,
+    Json,
+}
+
+impl DataFormat {
+    fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".json") {
+            DataFormat::Json
+        } else {
+            DataFormat::Csv
+        }
+    }
+}
+
+/*
+   BudgetImport Struct:
+   - added (usize): Categories that had no budget before the import.
+   - updated (usize): Categories whose existing budget was replaced.
+   - skipped (usize): CSV rows that couldn't be parsed.
+*/
+struct BudgetImport {
+    added: usize,
+    updated: usize,
+    skipped: usize,
+}
+
+/*
+   export_budgets Function:
+   - Writes the budgets map to `path`, sorted by category for stable output.
+   - CSV: a `Category,Budget` header followed by one row per category.
+   - JSON: a single object mapping each category to its budget.
+   - Returns how many budgets were written.
+*/
+fn export_budgets(
+    budgets: &HashMap<String, f64>,
+    path: &str,
+    format: DataFormat,
+) -> Result<usize, Box<dyn Error>> {
+    let sorted: BTreeMap<&String, &f64> = budgets.iter().collect();
+
+    match format {
+        DataFormat::Csv => {
+            let mut wtr = Writer::from_path(path)?;
+            wtr.write_record(["Category", "Budget"])?;
+            for (category, budget) in &sorted {
+                wtr.write_record([category.as_str(), &budget.to_string()])?;
+            }
+            wtr.flush()?;
+        }
+        DataFormat::Json => {
+            fs::write(path, serde_json::to_string_pretty(&sorted)?)?;
+        }
+    }
+
+    Ok(sorted.len())
+}
+
+/*
+   import_budgets Function:
+   - Reads budgets from `path` (format picked by DataFormat::from_path) and sets them with
+     ExpenseTracker::set_budget, replacing the budget of categories that already have one.
+     Categories go through normalize_category, like typed-in ones.
+   - Entries with a blank category (see validate_category) or, in a CSV, an amount that isn't
+     a finite number are skipped and counted; a JSON file has to parse as a whole.
+   - Returns the added/updated/skipped counts so the caller can report them.
+*/
+fn import_budgets(
+    tracker: &mut ExpenseTracker,
+    path: &str,
+) -> Result<BudgetImport, Box<dyn Error>> {
+    let mut parsed: Vec<(String, f64)> = Vec::new();
+    let mut skipped = 0;
+
+    match DataFormat::from_path(path) {
+        DataFormat::Csv => {
+            let mut rdr = csv::Reader::from_path(path)?;
+            for record in rdr.records() {
+                let record = record?;
+                let category = record.get(0).unwrap_or("");
+                let budget = record.get(1).and_then(|b| b.trim().parse::<f64>().ok());
+                match budget {
+                    Some(budget) => parsed.push((category.to_string(), budget)),
+                    None => skipped += 1,
+                }
+            }
+        }
+        DataFormat::Json => {
+            let map: HashMap<String, f64> = serde_json::from_str(&fs::read_to_string(path)?)?;
+            parsed.extend(map);
+        }
+    }
+
+    let mut result = BudgetImport {
+        added: 0,
+        updated: 0,
+        skipped,
+    };
+    for (category, budget) in parsed {
+        if validate_category(&category).is_err() {
+            result.skipped += 1;
+            continue;
+        }
+        let category = normalize_category(&category);
+        let existed = tracker.budgets.contains_key(&category);
+        let (kind, amount) = BudgetKind::of(budget);
+        if tracker.set_budget(&category, kind, amount).is_err() {
+            result.skipped += 1;
+            continue;
+        }
+        if existed {
+            result.updated += 1;
+        } else {
+            result.added += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+// Function to ask for a format and file name, then export the budgets
+fn export_budgets_menu(budgets: &HashMap<String, f64>) -> PromptResult {
+    if budgets.is_empty() {
+        println!("\n📂 No budgets to export.");
+        return Ok(());
+    }
+
+    let formats = vec!["CSV", "JSON"];
+    let choice = Select::new()
+        .with_prompt("Choose an export format")
+        .default(0)
+        .items(&formats)
+        .interact()?;
+    let (format, default_path) = match choice {
+        0 => (DataFormat::Csv, "budgets_export.csv"),
+        _ => (DataFormat::Json, "budgets_export.json"),
+    };
+
+    let path: String = Input::new()
+        .with_prompt("Enter the file to export to")
+        .default(default_path.to_string())
+        .interact_text()?;
+
+    match export_budgets(budgets, &path, format) {
+        Ok(count) => println!("📁 Exported {} budget(s) to `{}`", count, path),
+        Err(e) => println!("⚠️ Failed to export budgets: {}", e),
+    }
+    Ok(())
+}
+
+// Function to ask for a file name, import the budgets in it and report the counts
+fn import_budgets_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let path: String = Input::new()
+        .with_prompt("Enter the CSV or JSON file to import budgets from")
+        .interact_text()?;
+
+    match import_budgets(tracker, &path) {
+        Ok(result) => {
+            println!(
+                "✅ Imported {} budget(s): {} new, {} updated, {} skipped.",
+                result.added + result.updated,
+                result.added,
+                result.updated,
+                result.skipped
+            )
+        }
+        Err(e) => println!("⚠️ Failed to import budgets: {}", e),
+    }
+    Ok(())
+}
+
+/*
+   load_budgets Function:
+   - Reads the budgets saved next to the data file (a JSON object of category -> budget,
+     the same shape as a JSON budget export).
+   - A missing file means no budgets have been set yet; a malformed one is moved aside by
+     read_companion and starts without budgets, while an unreadable one is an error, like
+     load_json_expenses does for the data file.
+*/
+fn load_budgets(
+    storage: &dyn Storage,
+    path: &Path,
+) -> Result<HashMap<String, f64>, Box<dyn Error>> {
+    Ok(read_companion(storage, path, "budgets")?.unwrap_or_default())
+}
+
+/*
+   save_budgets Function:
+   - Writes the budgets next to the data file as JSON sorted by category, the same shape as
+     a JSON budget export.
+   - Like save_trash, skips writing when there are no budgets and no file exists yet.
+*/
+fn save_budgets(
+    storage: &dyn Storage,
+    budgets: &HashMap<String, f64>,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let sorted: BTreeMap<&String, &f64> = budgets.iter().collect();
+    write_companion(storage, &sorted, sorted.is_empty(), path)
+}
+
+/*
+   load_monthly_budget / save_monthly_budget Functions:
+   - Keep the overall monthly budget in its own file next to the data file (a bare number),
+     so the budgets file stays a plain category map.
+   - A missing or malformed file (moved aside by read_companion) means no overall budget;
+     like save_budgets, nothing is written while there is none and no file exists yet.
+*/
+fn load_monthly_budget(storage: &dyn Storage, path: &Path) -> Result<Option<f64>, Box<dyn Error>> {
+    Ok(read_companion(storage, path, "overall monthly budget")?.flatten())
+}
+
+fn save_monthly_budget(
+    storage: &dyn Storage,
+    monthly_budget: Option<f64>,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    write_companion(storage, &monthly_budget, monthly_budget.is_none(), path)
+}
+
+/*
+   load_rates / save_rates Functions:
+   - Keep the exchange rates set in the app in their own file next to the data file, in the
+     same JSON shape as rates_file, so they are still there in the next session.
+   - A missing or malformed file (moved aside by read_companion) means no rates yet; nothing
+     is written while there are no rates and no file exists yet.
+*/
+fn load_rates(storage: &dyn Storage, path: &Path) -> Result<StaticRateProvider, Box<dyn Error>> {
+    Ok(read_companion(storage, path, "exchange rates")?
+        .unwrap_or_else(|| StaticRateProvider::new(BASE_CURRENCY)))
+}
+
+fn save_rates(
+    storage: &dyn Storage,
+    rates: &StaticRateProvider,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    write_companion(storage, rates, rates.rates.is_empty(), path)
+}
+
+/*
+   parse_input_date Function:
+   - Parses a date typed by the user with the configured chrono format
+     (e.g. "%Y-%m-%d", "%m/%d/%Y" or "%d/%m/%Y").
+   - Surrounding whitespace is ignored.
+   - On mismatch returns a message that shows the expected format in plain terms.
+*/
+fn parse_input_date(input: &str, format: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(input.trim(), format).map_err(|_| {
+        format!(
+            "Invalid date '{}'. Expected format: {}.",
+            input.trim(),
+            date_format_hint(format)
+        )
+    })
+}
+
+/*
+   parse_expense_timestamp Function:
+   - Blank input means "now" and returns `now` unchanged.
+   - Anything else is parsed with parse_input_date and stamped with date_to_timestamp.
+   - A date before MIN_PLAUSIBLE_YEAR is an error; one far in the future is returned as is,
+     so the caller can ask with confirm_future_date.
+   - Kept free of prompts so the parsing rules can be checked on their own.
+*/
+fn parse_expense_timestamp(
+    input: &str,
+    format: &str,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, String> {
+    if input.trim().is_empty() {
+        return Ok(now);
+    }
+    let timestamp = parse_input_date(input, format).map(date_to_timestamp)?;
+    if !is_plausible_date(timestamp) && timestamp < now {
+        return Err(format!(
+            "'{}' is before {}. Please check the year.",
+            input.trim(),
+            MIN_PLAUSIBLE_YEAR
+        ));
+    }
+    Ok(timestamp)
+}
+
+// False for dates before MIN_PLAUSIBLE_YEAR or more than FUTURE_WARNING_DAYS after today,
+// which usually means a mistyped year
+fn is_plausible_date(timestamp: DateTime<Utc>) -> bool {
+    let latest = (Utc::now() + chrono::Duration::days(FUTURE_WARNING_DAYS)).date_naive();
+    timestamp.year() >= MIN_PLAUSIBLE_YEAR && timestamp.date_naive() <= latest
+}
+
+/*
+   confirm_future_date Function:
+   - Returns true straight away for a plausible date.
+   - For one more than FUTURE_WARNING_DAYS ahead, says how far ahead it is and asks whether
+     to keep it (default no), since it would otherwise sit unnoticed in a future month.
+*/
+fn confirm_future_date(timestamp: DateTime<Utc>, display_format: &str) -> PromptResult<bool> {
+    if is_plausible_date(timestamp) {
+        return Ok(true);
+    }
+    let days = (timestamp.date_naive() - Utc::now().date_naive()).num_days();
+    Confirm::new()
+        .with_prompt(format!(
+            "⚠️ {} is {} day(s) in the future — keep this date?",
+            format_local(timestamp, display_format),
+            days
+        ))
+        .default(false)
+        .interact()
+}
+
+// A date without a time is stamped at noon UTC so it stays on the same day in most timezones
+fn date_to_timestamp(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(12, 0, 0).unwrap().and_utc()
+}
+
+// Turns a chrono format like "%d/%m/%Y" into a readable hint like "DD/MM/YYYY"
+fn date_format_hint(format: &str) -> String {
+    format
+        .replace("%Y", "YYYY")
+        .replace("%y", "YY")
+        .replace("%m", "MM")
+        .replace("%d", "DD")
+        .replace("%b", "Mon")
+        .replace("%B", "Month")
+}
+
+/*
+   monthly_category_history Function:
+   - Looks at the `months` complete months before (year, month), newest first.
+   - For every category seen in that window, returns one total per month, using
+     category_totals for the grouping. Months without spending in a category count as 0.0,
+     so averages reflect how often the category actually comes up.
+*/
+fn monthly_category_history(
+    expenses: &[Expense],
+    year: i32,
+    month: u32,
+    months: u32,
+    rounding: Rounding,
+) -> HashMap<String, Vec<f64>> {
+    let mut history: HashMap<String, Vec<f64>> = HashMap::new();
+    let (mut y, mut m) = (year, month);
+
+    for i in 0..months as usize {
+        (y, m) = previous_month(y, m);
+        let totals = category_totals(expenses.iter().filter(|e| in_month(e, y, m)), rounding);
+        for (category, total) in totals {
+            history
+                .entry(category)
+                .or_insert_with(|| vec![0.0; months as usize])[i] = total;
+        }
+    }
+
+    history
+}
+
+/*
+   budget_wizard Function:
+   - Builds the spending history of the last WIZARD_MONTHS months per category.
+   - For each category (A-Z) shows the average and maximum monthly spend and suggests
+     the average + 10% as a budget, which the user can accept or adjust (0 skips it).
+   - After a final confirmation, applies all accepted budgets at once.
+*/
+fn budget_wizard(tracker: &mut ExpenseTracker) -> PromptResult {
+    let now = Utc::now();
+    let history = monthly_category_history(
+        &tracker.expenses,
+        now.year(),
+        now.month(),
+        WIZARD_MONTHS,
+        tracker.config.rounding,
+    );
+
+    if history.is_empty() {
+        println!(
+            "\n📂 No spending in the last {} months to base budgets on.",
+            WIZARD_MONTHS
+        );
+        return Ok(());
+    }
+
+    let mut categories: Vec<&String> = history.keys().collect();
+    categories.sort();
+
+    println!(
+        "\n🧙 Budget Wizard (based on the last {} months)",
+        WIZARD_MONTHS
+    );
+    let mut accepted: Vec<(String, f64)> = Vec::new();
+
+    for category in categories {
+        let totals = &history[category];
+        let rounding = tracker.config.rounding;
+        let average = rounding.round_cents(totals.iter().sum::<f64>() / totals.len() as f64);
+        let max = totals.iter().cloned().fold(0.0, f64::max);
+        let suggestion = rounding.round_cents(average * 1.1);
+
+        println!(
+            "\n{}: average {}/month, max {}",
+            category.green(),
+            format_money(average, &tracker.config.default_currency),
+            format_money(max, &tracker.config.default_currency)
+        );
+        let budget: f64 = Input::new()
+            .with_prompt(format!("Budget for '{}' (0 to skip)", category))
+            .default(suggestion)
+            .interact_text()?;
+
+        if budget > 0.0 {
+            accepted.push((category.clone(), budget));
+        }
+    }
+
+    if accepted.is_empty() {
+        println!("\n⚠️ No budgets accepted. Nothing changed.");
+        return Ok(());
+    }
+
+    let apply = Confirm::new()
+        .with_prompt(format!("Apply {} budget(s)?", accepted.len()))
+        .default(true)
+        .interact()?;
+
+    if apply {
+        let mut applied = 0;
+        for (category, budget) in &accepted {
+            match tracker.set_budget(category, BudgetKind::Limit, *budget) {
+                Ok(()) => applied += 1,
+                Err(e) => println!("⚠️ {} ({})", e, category),
+            }
+        }
+        println!("✅ {} budget(s) applied.", applied);
+    } else {
+        println!("⚠️ Budgets discarded.");
+    }
+    Ok(())
+}
+
+// Returns the number of days in the given month (28–31)
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+// Moves a timestamp into another month, keeping the day (clamped to the month's length) and time
+fn shift_to_month(timestamp: DateTime<Utc>, year: i32, month: u32) -> DateTime<Utc> {
+    let day = timestamp.day().min(days_in_month(year, month));
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_time(timestamp.time())
+        .and_utc()
+}
+
+/*
+   post_due_recurring Function:
+   - For every recurring template not yet posted this month whose day has come (the day is
+     clamped to the month's length, so 31 means the last day), adds the expense dated on
+     that day and marks the template as posted for the month.
+   - Prints one line per posted expense. They are added with ExpenseTracker::add_all, so
+     one undo takes them all back.
+*/
+fn post_due_recurring(tracker: &mut ExpenseTracker, today: NaiveDate) {
+    let key = format!("{:04}-{:02}", today.year(), today.month());
+    let last_day = days_in_month(today.year(), today.month());
+    let mut posted: Vec<Expense> = Vec::new();
+
+    for i in 0..tracker.recurring.len() {
+        let template = tracker.recurring[i].clone();
+        let day = template.day.clamp(1, last_day);
+        if template.last_posted.as_deref() == Some(key.as_str()) || today.day() < day {
+            continue;
+        }
+
+        let date = NaiveDate::from_ymd_opt(today.year(), today.month(), day).unwrap();
+        let expense = Expense {
+            id: 0,
+            amount: template.amount,
+            currency: template.currency,
+            kind: EntryKind::Expense,
+            category: template.category,
+            description: "Recurring".to_string(),
+            timestamp: date_to_timestamp(date),
+            split: Vec::new(),
+            payment_method: default_payment_method(),
+            tags: Vec::new(),
+            labels: Vec::new(),
+            note: String::new(),
+        };
+        println!(
+            "📆 Posted recurring expense: {} - {}",
+            expense.category,
+            format_money(expense.amount, &expense.currency)
+        );
+        posted.push(expense);
+        tracker.recurring[i].last_posted = Some(key.clone());
+    }
+    tracker.add_all("recurring posting", posted);
+}
+
+/*
+   recurring_menu Function:
+   - Add: prompts for category, amount, currency and day of month and stores a template.
+     If that day has already come this month it is posted right away.
+   - List: shows every template with its day and the last month it was posted.
+*/
+fn recurring_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let choices = vec!["➕ Add Recurring Expense", "📋 List Recurring Expenses"];
+    let selection = Select::new()
+        .with_prompt("📆 Recurring Expenses")
+        .default(0)
+        .items(&choices)
+        .interact()?;
+
+    if selection == 1 {
+        if tracker.recurring.is_empty() {
+            println!("\n📂 No recurring expenses yet.");
+            return Ok(());
+        }
+        println!("\n📆 Recurring Expenses:");
+        println!("-------------------------");
+        for template in &tracker.recurring {
+            println!(
+                "{} - {} on day {} (last posted: {})",
+                template.category.green(),
+                format_money(template.amount, &template.currency),
+                template.day,
+                template.last_posted.as_deref().unwrap_or("never")
+            );
+        }
+        println!("-------------------------");
+        return Ok(());
+    }
+
+    let category: String = Input::new()
+        .with_prompt("Enter category:")
+        .validate_with(|category: &String| validate_category(category))
+        .interact_text()?;
+    let amount: f64 = Input::new()
+        .with_prompt("Enter amount:")
+        .validate_with(|amount: &f64| validate_amount(*amount).map(|_| ()))
+        .interact_text()?;
+    let currency: String = Input::new()
+        .with_prompt("Enter currency code:")
+        .default(tracker.config.default_currency.clone())
+        .interact_text()?;
+    let day: u32 = Input::new()
+        .with_prompt("Day of the month it is due (1-31):")
+        .validate_with(|day: &u32| {
+            if (1..=31).contains(day) {
+                Ok(())
+            } else {
+                Err("Enter a day between 1 and 31.")
+            }
+        })
+        .interact_text()?;
+
+    tracker.recurring.push(RecurringTemplate {
+        category: normalize_category(&category),
+        amount,
+        currency: currency.trim().to_uppercase(),
+        day,
+        last_posted: None,
+    });
+    tracker.unsaved_changes = true;
+    println!("✅ Recurring expense added.");
+    post_due_recurring(tracker, Utc::now().date_naive());
+    Ok(())
+}
+
+/*
+   load_recurring Function:
+   - Reads the recurring templates saved next to the data file.
+   - A missing file means none have been set up; a malformed one is moved aside by
+     read_companion and starts without any.
+*/
+fn load_recurring(
+    storage: &dyn Storage,
+    path: &Path,
+) -> Result<Vec<RecurringTemplate>, Box<dyn Error>> {
+    Ok(read_companion(storage, path, "recurring expenses")?.unwrap_or_default())
+}
+
+// Writes the recurring templates next to the data file (skipped while there are none and no file yet)
+fn save_recurring(
+    storage: &dyn Storage,
+    recurring: &[RecurringTemplate],
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    write_companion(storage, recurring, recurring.is_empty(), path)
+}
+
+/*
+   clone_candidates Function:
+   - Takes last month's expenses (relative to year/month) whose category is in `categories`.
+   - Copies each one into the given month with shift_to_month.
+   - Leaves out entries the month already has, i.e. an expense with the same category and
+     amount, so running it twice doesn't duplicate the bills.
+*/
+fn clone_candidates(
+    expenses: &[Expense],
+    categories: &[String],
+    year: i32,
+    month: u32,
+) -> Vec<Expense> {
+    let (last_year, last_month) = previous_month(year, month);
+    let this_month: Vec<&Expense> = expenses
+        .iter()
+        .filter(|e| in_month(e, year, month))
+        .collect();
+
+    expenses
+        .iter()
+        .filter(|e| in_month(e, last_year, last_month) && categories.contains(&e.category))
+        .filter(|e| {
+            !this_month
+                .iter()
+                .any(|t| t.category == e.category && t.amount == e.amount)
+        })
+        .map(|e| Expense {
+            timestamp: shift_to_month(e.timestamp, year, month),
+            ..e.clone()
+        })
+        .collect()
+}
+
+/*
+   clone_last_month Function:
+   - Lists the categories used last month and lets the user pick some (e.g. fixed bills).
+   - Previews the entries clone_candidates would copy into this month.
+   - Adds them after confirmation with ExpenseTracker::add_all, each with a fresh ID and
+     under one undo point.
+*/
+fn clone_last_month(tracker: &mut ExpenseTracker) -> PromptResult {
+    let now = Utc::now();
+    let (last_year, last_month) = previous_month(now.year(), now.month());
+
+    let mut categories: Vec<String> = tracker
+        .expenses
+        .iter()
+        .filter(|e| in_month(e, last_year, last_month))
+        .map(|e| e.category.clone())
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+    categories.sort();
+
+    if categories.is_empty() {
+        println!("\n📂 No expenses recorded last month.");
+        return Ok(());
+    }
+
+    let picked = MultiSelect::new()
+        .with_prompt("Select the categories to copy (space to toggle, enter to confirm)")
+        .items(&categories)
+        .interact()?;
+    let picked: Vec<String> = picked.into_iter().map(|i| categories[i].clone()).collect();
+
+    if picked.is_empty() {
+        println!("⚠️ No categories selected. Nothing copied.");
+        return Ok(());
+    }
+
+    let candidates = clone_candidates(&tracker.expenses, &picked, now.year(), now.month());
+    if candidates.is_empty() {
+        println!("✅ This month already has all of those entries. Nothing to copy.");
+        return Ok(());
+    }
+
+    println!("\n🔁 Entries to copy:");
+    view_expenses(
+        &candidates,
+        tracker.config.page_size,
+        &tracker.config.date_display_format,
+        tracker.config.rounding,
+    );
+
+    let confirm = Confirm::new()
+        .with_prompt(format!(
+            "Copy {} entries into this month?",
+            candidates.len()
+        ))
+        .default(true)
+        .interact()?;
+
+    if confirm {
+        let count = tracker.add_all("copy", candidates);
+        println!("✅ {} entries copied.", count);
+    } else {
+        println!("⚠️ Nothing copied.");
+    }
+    Ok(())
+}
+
+/*
+   export_to_parquet Function (needs the `parquet` feature):
+   - Builds one Arrow record batch with typed columns:
+       • id: UInt64
+       • amount: Float64
+       • currency: Utf8
+       • category: Utf8
+       • description: Utf8 (empty string when there is none)
+       • timestamp: Timestamp in microseconds, UTC
+   - Writes it to `path` as a Snappy-compressed Parquet file for pandas/Polars.
+*/
+#[cfg(feature = "parquet")]
+fn export_to_parquet(expenses: &[Expense], path: &str) -> Result<(), Box<dyn Error>> {
+    use arrow_array::{
+        ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt64Array,
+    };
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("amount", DataType::Float64, false),
+        Field::new("currency", DataType::Utf8, false),
+        Field::new("category", DataType::Utf8, false),
+        Field::new("description", DataType::Utf8, false),
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+    ]));
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(expenses.iter().map(|e| e.id))),
+        Arc::new(Float64Array::from_iter_values(
+            expenses.iter().map(|e| e.amount),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            expenses.iter().map(|e| e.currency.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            expenses.iter().map(|e| e.category.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            expenses.iter().map(|e| e.description.as_str()),
+        )),
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                expenses.iter().map(|e| e.timestamp.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+// Function to ask for a file name and export the expenses to Parquet
+#[cfg(feature = "parquet")]
+fn export_to_parquet_menu(expenses: &[Expense]) -> PromptResult {
+    let path: String = Input::new()
+        .with_prompt("Enter the Parquet file to export to")
+        .default("expenses.parquet".to_string())
+        .interact_text()?;
+
+    match export_to_parquet(expenses, &path) {
+        Ok(()) => println!("📦 {} expenses exported to `{}`", expenses.len(), path),
+        Err(e) => println!("⚠️ Failed to export: {}", e),
+    }
+    Ok(())
+}
+
+// Without the `parquet` feature the menu entry just explains how to enable it
+#[cfg(not(feature = "parquet"))]
+fn export_to_parquet_menu(_expenses: &[Expense]) -> PromptResult {
+    println!("⚠️ Parquet export isn't included in this build. Rebuild with `cargo build --features parquet`.");
+    Ok(())
+}
+
+/*
+   CategoryCompletion Struct:
+   - categories (Vec<String>): Known category names, sorted.
+   - Implements dialoguer's Completion so pressing Tab in a prompt completes the
+     first category that starts with what was typed (ignoring case).
+*/
+struct CategoryCompletion {
+    categories: Vec<String>,
+}
+
+impl CategoryCompletion {
+    // Collects the distinct, non-uncategorized categories in use
+    fn from_expenses(expenses: &[Expense]) -> Self {
+        let mut categories: Vec<String> = expenses
+            .iter()
+            .filter(|e| !is_uncategorized(e))
+            .map(|e| e.category.clone())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+        categories.sort();
+        Self { categories }
+    }
+}
+
+impl Completion for CategoryCompletion {
+    fn get(&self, input: &str) -> Option<String> {
+        let input = input.to_lowercase();
+        self.categories
+            .iter()
+            .find(|c| c.to_lowercase().starts_with(&input))
+            .cloned()
+    }
+}
+
+// Returns true for expenses without a real category (blank or "Uncategorized")
+fn is_uncategorized(expense: &Expense) -> bool {
+    let category = expense.category.trim();
+    category.is_empty() || category.eq_ignore_ascii_case("Uncategorized")
+}
+
+/*
+   merge_categories_menu Function:
+   - Picks a source and a target from the categories in use, then moves every expense in the
+     source (matched ignoring case, so "food" also takes "FOOD") to the target with
+     ExpenseTracker::merge_category.
+   - Reports how many expenses moved and what happened to the source's budget, if it had one.
+*/
+fn merge_categories_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let categories = CategoryCompletion::from_expenses(&tracker.expenses).categories;
+    if categories.len() < 2 {
+        println!("📂 There need to be at least two categories to merge.");
+        return Ok(());
+    }
+
+    let source = categories[Select::new()
+        .with_prompt("Category to merge away")
+        .default(0)
+        .items(&categories)
+        .interact()?]
+    .clone();
+    let targets: Vec<&String> = categories.iter().filter(|c| **c != source).collect();
+    let target = targets[Select::new()
+        .with_prompt(format!("Merge '{}' into", source))
+        .default(0)
+        .items(&targets)
+        .interact()?]
+    .clone();
+
+    let source_budget = tracker
+        .budgets
+        .keys()
+        .any(|k| k.eq_ignore_ascii_case(&source) && *k != target);
+    let target_budget = tracker.budgets.contains_key(&target);
+    let moved = tracker.merge_category(&source, &target);
+    println!(
+        "✅ Moved {} expense(s) from '{}' to '{}'.",
+        moved, source, target
+    );
+    if source_budget {
+        if target_budget {
+            println!(
+                "⚠️ '{}' keeps its own budget; the budget for '{}' was removed.",
+                target, source
+            );
+        } else {
+            println!(
+                "✅ The budget for '{}' now applies to '{}'.",
+                source, target
+            );
+        }
+    }
+    Ok(())
+}
+
+/*
+   rename_category_menu Function:
+   - Prompts for the category to rename (Tab completes the ones in use, matched ignoring case)
+     and its new name, which can't be blank and is normalized like any typed category.
+   - Shows how many expenses would change and asks for confirmation before renaming them and
+     the category's budget with ExpenseTracker::rename_category.
+*/
+fn rename_category_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let completion = CategoryCompletion::from_expenses(&tracker.expenses);
+    let old: String = Input::new()
+        .with_prompt("Category to rename")
+        .completion_with(&completion)
+        .validate_with(|category: &String| validate_category(category))
+        .interact_text()?;
+    let old = old.trim().to_string();
+
+    if !tracker
+        .expenses
+        .iter()
+        .any(|e| e.category.eq_ignore_ascii_case(&old))
+    {
+        println!("⚠️ No expenses in category '{}'.", old);
+        return Ok(());
+    }
+
+    let new: String = Input::new()
+        .with_prompt(format!("New name for '{}'", old))
+        .validate_with(|category: &String| validate_category(category))
+        .interact_text()?;
+    let new = normalize_category(&new);
+
+    // Expenses already spelled exactly like the new name stay as they are.
+    let affected = tracker
+        .expenses
+        .iter()
+        .filter(|e| e.category.eq_ignore_ascii_case(&old) && e.category != new)
+        .count();
+    if affected == 0 {
+        println!("⚠️ Every expense in '{}' is already called '{}'.", old, new);
+        return Ok(());
+    }
+
+    let confirmed = Confirm::new()
+        .with_prompt(format!(
+            "Rename '{}' to '{}' on {} expense(s)?",
+            old, new, affected
+        ))
+        .default(true)
+        .interact()?;
+    if !confirmed {
+        println!("👍 Nothing renamed.");
+        return Ok(());
+    }
+
+    let renamed = tracker.rename_category(&old, &new);
+    println!(
+        "✅ Renamed '{}' to '{}' on {} expense(s).",
+        old, new, renamed
+    );
+    Ok(())
+}
+
+/*
+   cleanup_uncategorized Function:
+   - Finds every expense with a blank or "Uncategorized" category; stops early if there are none.
+   - Walks through them one by one, showing the amount, date and description, and asks for a category
+     (Tab completes known categories, blank skips the entry).
+   - Applies each answer right away and reports how many were categorized; any change marks
+     the tracker as unsaved.
+*/
+fn cleanup_uncategorized(tracker: &mut ExpenseTracker) -> PromptResult {
+    let display_format = tracker.config.date_display_format.clone();
+    let expenses = &mut tracker.expenses;
+    let pending: Vec<usize> = expenses
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| is_uncategorized(e))
+        .map(|(i, _)| i)
+        .collect();
+
+    if pending.is_empty() {
+        println!("\n✅ No uncategorized expenses. Nothing to clean up!");
+        return Ok(());
+    }
+
+    let completion = CategoryCompletion::from_expenses(expenses);
+    println!(
+        "\n🧹 {} uncategorized expense(s) found. Press Tab to complete a category, Enter on a blank line to skip.",
+        pending.len()
+    );
+
+    let mut categorized = 0;
+    for (n, &i) in pending.iter().enumerate() {
+        let expense = &mut expenses[i];
+        println!(
+            "\n[{}/{}] #{} - {} - {} {}",
+            n + 1,
+            pending.len(),
+            i + 1,
+            format_money(expense.amount, &expense.currency),
+            format_local(expense.timestamp, &display_format).purple(),
+            expense.description
+        );
+
+        let category: String = Input::new()
+            .with_prompt("Category")
+            .allow_empty(true)
+            .completion_with(&completion)
+            .interact_text()?;
+
+        if !category.trim().is_empty() {
+            expense.category = normalize_category(&category);
+            categorized += 1;
+        }
+    }
+
+    println!(
+        "\n✅ Categorized {} of {} expense(s).",
+        categorized,
+        pending.len()
+    );
+    tracker.unsaved_changes |= categorized > 0;
+    Ok(())
+}
+
+/*
+   RateProvider Trait:
+   - rate(from, to): How many units of `to` one unit of `from` is worth, or None if unknown.
+   - Lets currency conversion work with any source of rates (a static file today,
+     an online service later) without changing convert_total.
+*/
+trait RateProvider {
+    fn rate(&self, from: &str, to: &str) -> Option<f64>;
+}
+
+/*
+   StaticRateProvider Struct:
+   - base (String): Currency the rates are quoted against.
+   - rates (HashMap<String, f64>): Units of each currency per one unit of `base`.
+   Loaded from a JSON file such as:
+       { "base": "USD", "rates": { "EUR": 0.92, "GBP": 0.79 } }
+   This is the one representation of exchange rates; the rates typed in with
+   set_exchange_rate are kept the same way (tracker.rates, based on BASE_CURRENCY).
+*/
+#[derive(Serialize, Deserialize, Debug)]
+struct StaticRateProvider {
+    base: String,
+    rates: HashMap<String, f64>,
+}
+
+impl StaticRateProvider {
+    fn new(base: &str) -> Self {
+        Self {
+            base: base.to_string(),
+            rates: HashMap::new(),
+        }
+    }
+
+    fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    // Units of `currency` per one unit of the base currency (1.0 for the base itself)
+    fn per_base(&self, currency: &str) -> Option<f64> {
+        if currency.eq_ignore_ascii_case(&self.base) {
+            return Some(1.0);
+        }
+        self.rates
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(currency))
+            .map(|(_, &rate)| rate)
+    }
+}
+
+impl RateProvider for StaticRateProvider {
+    // Cross rates go through the base currency: from -> base -> to
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(1.0);
+        }
+        let from_rate = self.per_base(from)?;
+        let to_rate = self.per_base(to)?;
+        (from_rate != 0.0).then(|| to_rate / from_rate)
+    }
+}
+
+// Function to set (or replace) the exchange rate of a currency, as units of it per BASE_CURRENCY
+fn set_exchange_rate(tracker: &mut ExpenseTracker) -> PromptResult {
+    let currency: String = Input::new()
+        .with_prompt("Enter currency code (e.g. EUR)")
+        .interact_text()?;
+    let currency = currency.trim().to_uppercase();
+
+    if currency == BASE_CURRENCY {
+        println!(
+            "⚠️ {} is the base currency; its rate is always 1.",
+            BASE_CURRENCY
+        );
+        return Ok(());
+    }
+
+    let rate: f64 = Input::new()
+        .with_prompt(format!(
+            "How many {} is 1 {} worth?",
+            currency, BASE_CURRENCY
+        ))
+        .interact_text()?;
+
+    if rate <= 0.0 || !rate.is_finite() {
+        println!("⚠️ The rate must be a positive number. Nothing changed.");
+        return Ok(());
+    }
+
+    tracker.rates.rates.insert(currency.clone(), rate);
+    tracker.unsaved_changes = true;
+    println!("✅ Rate set: 1 {} = {} {}", BASE_CURRENCY, rate, currency);
+    Ok(())
+}
+
+/*
+   convert_total Function:
+   - Converts every (amount, currency) pair into `to` using the given provider and sums them,
+     each converted amount rounded to cents with `rounding` first, like category_totals.
+   - Pairs whose rate is unknown are left out of the total; their currency codes are
+     returned (once each) so the caller can warn instead of silently using 1.0.
+*/
+fn convert_total<'a, I>(
+    amounts: I,
+    to: &str,
+    provider: &dyn RateProvider,
+    rounding: Rounding,
+) -> (f64, Vec<String>)
+where
+    I: IntoIterator<Item = (f64, &'a str)>,
+{
+    let mut total = 0.0;
+    let mut missing: Vec<String> = Vec::new();
+
+    for (amount, currency) in amounts {
+        match provider.rate(currency, to) {
+            Some(rate) => total += rounding.round_cents(amount * rate),
+            None => {
+                if !missing.iter().any(|m| m == currency) {
+                    missing.push(currency.to_string());
+                }
+            }
+        }
+    }
+
+    (total, missing)
+}
+
+/*
+   convert_total_menu Function:
+   - Loads the static rates from config.rates_file.
+   - Asks for a target currency and prints the total of all expenses converted into it.
+   - Each expense is converted from its own currency.
+*/
+fn convert_total_menu(tracker: &ExpenseTracker) -> PromptResult {
+    let provider = match StaticRateProvider::from_file(&tracker.config.rates_file) {
+        Ok(provider) => provider,
+        Err(e) => {
+            println!(
+                "⚠️ Could not load exchange rates from `{}`: {}",
+                tracker.config.rates_file, e
+            );
+            return Ok(());
+        }
+    };
+
+    let target: String = Input::new()
+        .with_prompt("Convert the total to which currency? (e.g. EUR)")
+        .interact_text()?;
+    let target = target.trim().to_uppercase();
+
+    let amounts = tracker
+        .expenses
+        .iter()
+        .map(|e| (e.amount, e.currency.as_str()));
+    let (total, missing) = convert_total(amounts, &target, &provider, tracker.config.rounding);
+
+    if !missing.is_empty() {
+        println!(
+            "⚠️ No exchange rate from {} to {} in `{}`. Those amounts were left out.",
+            missing.join(", "),
+            target,
+            tracker.config.rates_file
+        );
+    }
+
+    println!("💱 Total Spending: {:.2} {}", total, target);
+    Ok(())
+}
+
+// Returns true if the expense carries the tag (ignoring case), see filter_by_tag
+fn has_tag(expense: &Expense, tag: &str) -> bool {
+    expense.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+}
+
+// Returns true if the expense carries the label (ignoring case)
+fn has_label(expense: &Expense, label: &str) -> bool {
+    expense.labels.iter().any(|l| l.eq_ignore_ascii_case(label))
+}
+
+// Adds the label to the expense, or removes it if it is already there
+fn toggle_label(expense: &mut Expense, label: &str) {
+    if has_label(expense, label) {
+        expense.labels.retain(|l| !l.eq_ignore_ascii_case(label));
+    } else {
+        expense.labels.push(label.to_string());
+    }
+}
+
+/*
+   labels_menu Function:
+   - Small submenu for working with labels:
+       • Review & label: bulk-toggle a label on a filtered set of expenses.
+       • Filter by tag: list the expenses carrying a tag (entered when adding them) with their
+         subtotal (filter_by_tag).
+       • Label summary: total spending per label.
+*/
+fn labels_menu(tracker: &mut ExpenseTracker) -> PromptResult {
+    let choices = vec!["✏️ Review & Label", "🔍 Filter by Tag", "📊 Label Summary"];
+    let selection = Select::new()
+        .with_prompt("🏷️ Labels")
+        .default(0)
+        .items(&choices)
+        .interact()?;
+
+    match selection {
+        0 => review_labels(tracker),
+        1 => filter_by_tag(
+            &tracker.expenses,
+            &tracker.config.date_display_format,
+            tracker.config.rounding,
+        ),
+        _ => {
+            label_summary(&tracker.expenses, tracker.config.rounding);
+            Ok(())
+        }
+    }
+}
+
+/*
+   review_labels Function:
+   - Asks which category to review (blank for all expenses) and which label to apply.
+   - Shows the matching expenses in a checklist where those already carrying the label
+     start ticked; the user toggles entries with space.
+   - Applies the changes with toggle_label and reports how many were labeled/unlabeled;
+     any change marks the tracker as unsaved.
+*/
+fn review_labels(tracker: &mut ExpenseTracker) -> PromptResult {
+    let display_format = tracker.config.date_display_format.clone();
+    let expenses = &mut tracker.expenses;
+    let category: String = Input::new()
+        .with_prompt("Category to review (leave blank for all)")
+        .allow_empty(true)
+        .interact_text()?;
+    let category = category.trim();
+
+    let subset: Vec<usize> = expenses
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| category.is_empty() || e.category.eq_ignore_ascii_case(category))
+        .map(|(i, _)| i)
+        .collect();
+
+    if subset.is_empty() {
+        println!("\n⚠️ No expenses found to review.");
+        return Ok(());
+    }
+
+    let label: String = Input::new()
+        .with_prompt("Label to apply (e.g. vacation)")
+        .interact_text()?;
+    let label = label.trim().to_string();
+    if label.is_empty() {
+        println!("⚠️ Label cannot be empty. Nothing changed.");
+        return Ok(());
+    }
+
+    let items: Vec<String> = subset
+        .iter()
+        .map(|&i| {
+            let e = &expenses[i];
+            format!(
+                "#{} {} - {} - {}",
+                i + 1,
+                e.category,
+                format_local(e.timestamp, &display_format),
+                format_money(e.amount, &e.currency)
+            )
+        })
+        .collect();
+    let defaults: Vec<bool> = subset
+        .iter()
+        .map(|&i| has_label(&expenses[i], &label))
+        .collect();
+
+    let picked = MultiSelect::new()
+        .with_prompt(format!(
+            "Toggle '{}' on each expense (space to toggle, enter to confirm)",
+            label
+        ))
+        .items(&items)
+        .defaults(&defaults)
+        .interact()?;
+
+    let (mut added, mut removed) = (0, 0);
+    for (n, &i) in subset.iter().enumerate() {
+        let wanted = picked.contains(&n);
+        if wanted != defaults[n] {
+            toggle_label(&mut expenses[i], &label);
+            if wanted {
+                added += 1;
+            } else {
+                removed += 1;
+            }
+        }
+    }
+
+    println!(
+        "✅ '{}' added to {} and removed from {} expense(s).",
+        label, added, removed
+    );
+    tracker.unsaved_changes |= added + removed > 0;
+    Ok(())
+}
+
+/*
+   filter_by_tag Function:
+   - Prompts for a tag and lists every expense carrying it (any of its tags may match,
+     ignoring case), keeping their list numbers.
+   - Ends with a subtotal of the spending among them, per currency when several are used.
+*/
+fn filter_by_tag(expenses: &[Expense], display_format: &str, rounding: Rounding) -> PromptResult {
+    let tag: String = Input::new()
+        .with_prompt("Enter tag to filter by")
+        .interact_text()?;
+    let tag = tag.trim();
+
+    let matches: Vec<&Expense> = expenses.iter().filter(|e| has_tag(e, tag)).collect();
+
+    if matches.is_empty() {
+        println!("\n⚠️ No expense is tagged '{}'.", tag);
+        return Ok(());
+    }
+
+    println!("\n🏷️ Expenses tagged '{}':", tag);
+    print_subset(expenses, &matches, display_format);
+    println!("💰 Subtotal: {}", format_totals(&matches, rounding));
+    Ok(())
+}
+
+// Function to print the count and total spending per label, A-Z (per currency, see format_totals)
+fn label_summary(expenses: &[Expense], rounding: Rounding) {
+    let mut by_label: BTreeMap<String, Vec<&Expense>> = BTreeMap::new();
+    for expense in expenses {
+        for label in &expense.labels {
+            by_label
+                .entry(label.to_lowercase())
+                .or_default()
+                .push(expense);
+        }
+    }
+
+    if by_label.is_empty() {
+        println!("\n📂 No labeled expenses yet.");
+        return;
+    }
+
+    println!("\n🏷️ Label Summary:");
+    println!("-------------------------------------");
+    for (label, entries) in &by_label {
+        println!(
+            "Label: {}, {} expense(s), Total: {}",
+            label,
+            entries.len(),
+            format_totals(entries, rounding)
+        );
+    }
+    println!("-------------------------------------");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // An expense in `category` on 2024-05-10, with every other field at its default
+    fn expense(category: &str, amount: f64) -> Expense {
+        Expense {
+            id: 0,
+            amount,
+            currency: BASE_CURRENCY.to_string(),
+            kind: EntryKind::Expense,
+            category: category.to_string(),
+            description: String::new(),
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap(),
+            split: Vec::new(),
+            payment_method: UNKNOWN_PAYMENT_METHOD.to_string(),
+            tags: Vec::new(),
+            labels: Vec::new(),
+            note: String::new(),
+        }
+    }
+
+    // A tracker backed by MemoryStorage holding Food 10.00, Rent 500.00 and Travel 42.50
+    fn tracker() -> ExpenseTracker {
+        let mut tracker = ExpenseTracker::new();
+        tracker.add(expense("Food", 10.0));
+        tracker.add(expense("Rent", 500.0));
+        tracker.add(expense("Travel", 42.5));
+        tracker.undo = None;
+        tracker.unsaved_changes = false;
+        tracker
+    }
+
+    fn categories(expenses: &[Expense]) -> Vec<&str> {
+        expenses.iter().map(|e| e.category.as_str()).collect()
+    }
+
+    #[test]
+    fn add_assigns_increasing_ids_and_marks_unsaved() {
+        let mut tracker = tracker();
+        let id = tracker.add(expense("Books", 12.0));
+
+        assert_eq!(id, 4);
+        assert_eq!(tracker.expenses.last().unwrap().id, 4);
+        assert_eq!(
+            categories(&tracker.expenses),
+            ["Food", "Rent", "Travel", "Books"]
+        );
+        assert!(tracker.unsaved_changes);
+    }
+
+    #[test]
+    fn delete_by_index_removes_that_expense() {
+        let mut tracker = tracker();
+        let removed = tracker.delete_by_index(1).unwrap();
+
+        assert_eq!(removed.category, "Rent");
+        assert_eq!(categories(&tracker.expenses), ["Food", "Travel"]);
+        assert!(tracker.trash.is_empty());
+        assert!(tracker.unsaved_changes);
+    }
+
+    #[test]
+    fn delete_by_index_out_of_range_changes_nothing() {
+        let mut tracker = tracker();
+
+        assert!(tracker.delete_by_index(3).is_err());
+        assert_eq!(tracker.expenses.len(), 3);
+        assert!(tracker.undo.is_none());
+        assert!(!tracker.unsaved_changes);
+    }
+
+    #[test]
+    fn delete_with_soft_delete_moves_to_trash() {
+        let mut tracker = tracker();
+        tracker.config.soft_delete = true;
+        tracker.delete_indices(&[0, 2, 2]).unwrap();
+
+        assert_eq!(categories(&tracker.expenses), ["Rent"]);
+        assert_eq!(categories(&tracker.trash), ["Travel", "Food"]);
+    }
+
+    #[test]
+    fn delete_by_id_finds_the_expense_after_sorting() {
+        let mut tracker = tracker();
+        apply_sort(&mut tracker.expenses, SortOrder::AmountDesc);
+        let removed = tracker.delete_by_id(1).unwrap();
+
+        assert_eq!(removed.category, "Food");
+        assert_eq!(categories(&tracker.expenses), ["Rent", "Travel"]);
+        assert!(tracker.delete_by_id(1).is_err());
+    }
+
+    #[test]
+    fn edit_changes_one_field() {
+        let mut tracker = tracker();
+        tracker.edit(0, ExpenseEdit::Amount(11.25)).unwrap();
+        tracker
+            .edit(1, ExpenseEdit::Category("home office".to_string()))
+            .unwrap();
+        tracker
+            .edit(2, ExpenseEdit::Note("  flight to Lisbon ".to_string()))
+            .unwrap();
+
+        assert_eq!(tracker.expenses[0].amount, 11.25);
+        assert_eq!(tracker.expenses[1].category, "Home Office");
+        assert_eq!(tracker.expenses[2].note, "flight to Lisbon");
+        assert!(tracker.unsaved_changes);
+    }
+
+    #[test]
+    fn edit_refuses_invalid_amount_and_position() {
+        let mut tracker = tracker();
+
+        assert!(tracker.edit(0, ExpenseEdit::Amount(-5.0)).is_err());
+        assert!(tracker.edit(0, ExpenseEdit::Amount(f64::NAN)).is_err());
+        assert!(tracker
+            .edit(9, ExpenseEdit::Description("x".to_string()))
+            .is_err());
+        assert_eq!(tracker.expenses[0].amount, 10.0);
+        assert!(tracker.undo.is_none());
+        assert!(!tracker.unsaved_changes);
+    }
+
+    #[test]
+    fn set_and_remove_budget() {
+        let mut tracker = tracker();
+        tracker
+            .set_budget("Food", BudgetKind::Limit, 200.0)
+            .unwrap();
+        tracker
+            .set_budget("Savings", BudgetKind::Savings, 150.0)
+            .unwrap();
+
+        assert_eq!(tracker.budgets.get("Food"), Some(&200.0));
+        assert_eq!(tracker.budgets.get("Savings"), Some(&-150.0));
+        assert!(tracker.unsaved_changes);
+
+        tracker.unsaved_changes = false;
+        assert_eq!(tracker.remove_budget("Food"), Some(200.0));
+        assert!(tracker.unsaved_changes);
+
+        tracker.unsaved_changes = false;
+        assert_eq!(tracker.remove_budget("Food"), None);
+        assert!(!tracker.unsaved_changes);
+        assert_eq!(tracker.budgets.len(), 1);
+    }
+
+    #[test]
+    fn undo_last_reverts_delete_and_edit() {
+        let mut tracker = tracker();
+        tracker.delete_by_index(0).unwrap();
+        assert_eq!(tracker.undo_last(), Some("delete"));
+        assert_eq!(categories(&tracker.expenses), ["Food", "Rent", "Travel"]);
+
+        tracker.edit(1, ExpenseEdit::Amount(450.0)).unwrap();
+        assert_eq!(tracker.undo_last(), Some("edit"));
+        assert_eq!(tracker.expenses[1].amount, 500.0);
+
+        // One level only: a second undo has nothing left to restore.
+        assert_eq!(tracker.undo_last(), None);
+    }
+
+    #[test]
+    fn undo_last_brings_back_the_trash_too() {
+        let mut tracker = tracker();
+        tracker.config.soft_delete = true;
+        tracker.delete_by_id(3).unwrap();
+        tracker.undo_last();
+
+        assert_eq!(tracker.expenses.len(), 3);
+        assert!(tracker.trash.is_empty());
+    }
+
+    #[test]
+    fn saved_tracker_loads_back_from_its_storage() {
+        let mut tracker = tracker();
+        tracker.delete_by_index(0).unwrap();
+        tracker.storage.save(&tracker.expenses).unwrap();
+
+        let loaded = tracker.storage.load().unwrap();
+        assert_eq!(categories(&loaded), ["Rent", "Travel"]);
+        assert_eq!(loaded[1].id, 3);
+    }
+
+    // A fresh, empty folder under the system temp dir, unique to this test run
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "expense_tracker_test_{}_{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Expenses have no PartialEq, so compare what they serialize to
+    fn as_json(expenses: &[Expense]) -> String {
+        serde_json::to_string(expenses).unwrap()
+    }
+
+    // Entries that exercise every field: income, another currency, a split, tags, labels and a note
+    fn varied_expenses() -> Vec<Expense> {
+        let mut coffee = expense("Food", 3.5);
+        coffee.id = 1;
+        coffee.description = "Coffee".to_string();
+        coffee.tags = vec!["work".to_string(), "reimbursable".to_string()];
+
+        let mut dinner = expense("Dining", 84.2);
+        dinner.id = 2;
+        dinner.currency = "EUR".to_string();
+        dinner.payment_method = "Visa".to_string();
+        dinner.split = vec![
+            SplitShare {
+                person: SELF_NAME.to_string(),
+                ratio: 0.5,
+            },
+            SplitShare {
+                person: "Sam".to_string(),
+                ratio: 0.5,
+            },
+        ];
+        dinner.note = "Birthday dinner, Sam pays half".to_string();
+        dinner.labels = vec!["vacation".to_string()];
+
+        let mut salary = expense("Salary", 2500.0);
+        salary.id = 3;
+        salary.kind = EntryKind::Income;
+
+        vec![coffee, dinner, salary]
+    }
+
+    #[test]
+    fn json_and_memory_storage_load_back_what_they_saved() {
+        let dir = scratch_dir("storage_parity");
+        let json = JsonStorage {
+            path: dir.join("expenses.json").to_string_lossy().into_owned(),
+            backed_up: Cell::new(false),
+        };
+        let memory = MemoryStorage::default();
+        let expenses = varied_expenses();
+
+        json.save(&expenses).unwrap();
+        memory.save(&expenses).unwrap();
+
+        let from_json = json.load().unwrap();
+        let from_memory = memory.load().unwrap();
+        assert_eq!(as_json(&from_json), as_json(&expenses));
+        assert_eq!(as_json(&from_memory), as_json(&from_json));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn storages_replace_rather_than_append_on_save() {
+        let dir = scratch_dir("storage_replace");
+        let json = JsonStorage {
+            path: dir.join("expenses.json").to_string_lossy().into_owned(),
+            backed_up: Cell::new(false),
+        };
+        let memory = MemoryStorage::default();
+        let mut expenses = varied_expenses();
+
+        for storage in [&json as &dyn Storage, &memory] {
+            storage.save(&expenses).unwrap();
+        }
+        expenses.remove(1);
+        for storage in [&json as &dyn Storage, &memory] {
+            storage.save(&expenses).unwrap();
+            assert_eq!(as_json(&storage.load().unwrap()), as_json(&expenses));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_storages_load_nothing() {
+        let dir = scratch_dir("storage_empty");
+        let json = JsonStorage {
+            path: dir.join("expenses.json").to_string_lossy().into_owned(),
+            backed_up: Cell::new(false),
+        };
+
+        assert!(json.load().unwrap().is_empty());
+        assert!(MemoryStorage::default().load().unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Food 10.00 on May 10, Rent 500.00 on May 1, Travel 42.50 on May 20
+    fn unsorted() -> Vec<Expense> {
+        let dated = |category: &str, amount: f64, day: u32| {
+            let mut e = expense(category, amount);
+            e.timestamp = Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap();
+            e
+        };
+        vec![
+            dated("Food", 10.0, 10),
+            dated("rent", 500.0, 1),
+            dated("Travel", 42.5, 20),
+        ]
+    }
+
+    #[test]
+    fn apply_sort_orders_by_each_key() {
+        let cases = [
+            (SortOrder::AmountAsc, ["Food", "Travel", "rent"]),
+            (SortOrder::AmountDesc, ["rent", "Travel", "Food"]),
+            (SortOrder::Category, ["Food", "rent", "Travel"]),
+            (SortOrder::DateNewest, ["Travel", "Food", "rent"]),
+            (SortOrder::DateOldest, ["rent", "Food", "Travel"]),
+        ];
+        for (order, expected) in cases {
+            let mut expenses = unsorted();
+            apply_sort(&mut expenses, order);
+            assert_eq!(categories(&expenses), expected, "{:?}", order);
+        }
+    }
+
+    #[test]
+    fn load_tracker_applies_the_default_sort() {
+        let dir = scratch_dir("default_sort");
+        let data_path = dir.join("expenses.json").to_string_lossy().into_owned();
+        let config_path = dir.join("config.json").to_string_lossy().into_owned();
+        save_json_expenses(&unsorted(), &data_path, true).unwrap();
+
+        fs::write(&config_path, r#"{ "default_sort": "amount_desc" }"#).unwrap();
+        let tracker = load_tracker(&data_path, &config_path);
+        assert_eq!(categories(&tracker.expenses), ["rent", "Travel", "Food"]);
+
+        fs::write(&config_path, "{}").unwrap();
+        let tracker = load_tracker(&data_path, &config_path);
+        assert_eq!(categories(&tracker.expenses), ["Food", "rent", "Travel"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parse_input_date_follows_the_configured_format() {
+        assert_eq!(
+            parse_input_date("2024-03-07", "%Y-%m-%d"),
+            Ok(date(2024, 3, 7))
+        );
+        assert_eq!(
+            parse_input_date("03/07/2024", "%m/%d/%Y"),
+            Ok(date(2024, 3, 7))
+        );
+        assert_eq!(
+            parse_input_date("07/03/2024", "%d/%m/%Y"),
+            Ok(date(2024, 3, 7))
+        );
+        assert_eq!(
+            parse_input_date("  2024-03-07 ", "%Y-%m-%d"),
+            Ok(date(2024, 3, 7))
+        );
+    }
+
+    #[test]
+    fn parse_input_date_explains_the_expected_format() {
+        let error = parse_input_date("2024-03-07", "%d/%m/%Y").unwrap_err();
+        assert_eq!(
+            error,
+            "Invalid date '2024-03-07'. Expected format: DD/MM/YYYY."
+        );
+        assert!(parse_input_date("2024-02-30", "%Y-%m-%d").is_err());
+    }
+
+    #[test]
+    fn date_format_hint_spells_out_the_fields() {
+        assert_eq!(date_format_hint("%Y-%m-%d"), "YYYY-MM-DD");
+        assert_eq!(date_format_hint("%d %b %y"), "DD Mon YY");
+    }
+
+    // Writes `expenses` with export_to_csv into a scratch folder and returns the file's rows
+    fn exported_csv(
+        name: &str,
+        expenses: &[Expense],
+        columns: &[CsvColumn],
+        with_totals: bool,
+    ) -> Vec<Vec<String>> {
+        let dir = scratch_dir(name);
+        let path = dir.join(CSV_FILE).to_string_lossy().into_owned();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        export_to_csv(&refs, &path, columns, with_totals, Rounding::HalfUp).unwrap();
+        let rows = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(&path)
+            .unwrap()
+            .records()
+            .map(|record| record.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        rows
+    }
+
+    #[test]
+    fn csv_export_starts_each_row_with_the_expense_id() {
+        let mut expenses = varied_expenses();
+        expenses[0].id = 7;
+        expenses[2].id = 0;
+        let rows = exported_csv("csv_id", &expenses, &CsvColumn::ALL, false);
+
+        assert_eq!(rows[0][0], "Id");
+        let ids: Vec<&str> = rows[1..].iter().map(|row| row[0].as_str()).collect();
+        // An expense without an ID falls back to its row number.
+        assert_eq!(ids, ["7", "2", "3"]);
+    }
+
+    #[test]
+    fn format_percent_uses_the_configured_decimals() {
+        assert_eq!(format_percent(42.456, 0), "42%");
+        assert_eq!(format_percent(42.456, 1), "42.5%");
+        assert_eq!(format_percent(42.456, 2), "42.46%");
+        assert_eq!(format_percent(100.0, 1), "100.0%");
+    }
+
+    #[test]
+    fn percent_of_is_zero_for_an_empty_whole() {
+        assert_eq!(percent_of(25.0, 200.0), 12.5);
+        assert_eq!(percent_of(5.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn negative_budgets_are_savings_targets() {
+        assert_eq!(BudgetKind::of(300.0), (BudgetKind::Limit, 300.0));
+        assert_eq!(BudgetKind::of(-200.0), (BudgetKind::Savings, 200.0));
+        assert_eq!(BudgetKind::of(0.0), (BudgetKind::Limit, 0.0));
+    }
+
+    #[test]
+    fn budget_warning_for_savings_targets_warns_while_below() {
+        let warning = budget_warning("Savings", -200.0, 150.0, 0.8, "USD").unwrap();
+        assert!(warning.contains("$150.00"), "{}", warning);
+        assert!(warning.contains("savings target of $200.00"), "{}", warning);
+
+        assert_eq!(budget_warning("Savings", -200.0, 200.0, 0.8, "USD"), None);
+        assert_eq!(budget_warning("Savings", -200.0, 250.0, 0.8, "USD"), None);
+    }
+
+    #[test]
+    fn budget_warning_for_limits_warns_when_near_or_over() {
+        assert_eq!(budget_warning("Food", 100.0, 50.0, 0.8, "USD"), None);
+        let near = budget_warning("Food", 100.0, 85.0, 0.8, "USD").unwrap();
+        assert!(near.contains("spent $85.00 of your $100.00"), "{}", near);
+        let over = budget_warning("Food", 100.0, 100.01, 0.8, "USD").unwrap();
+        assert!(over.contains("exceeded your budget of $100.00"), "{}", over);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_export_reads_back_with_every_row() {
+        use arrow_array::{Array, Float64Array, StringArray, UInt64Array};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let dir = scratch_dir("parquet");
+        let path = dir.join("expenses.parquet");
+        let expenses = varied_expenses();
+        export_to_parquet(&expenses, &path.to_string_lossy()).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, expenses.len());
+
+        let batch = &batches[0];
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+        let ids = column("id");
+        let ids = ids.as_any().downcast_ref::<UInt64Array>().unwrap();
+        let amounts = column("amount");
+        let amounts = amounts.as_any().downcast_ref::<Float64Array>().unwrap();
+        let currencies = column("currency");
+        let currencies = currencies.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(ids.values().to_vec(), [1, 2, 3]);
+        assert_eq!(amounts.value(1), 84.2);
+        assert_eq!(currencies.value(1), "EUR");
+        assert_eq!(batch.schema().fields().len(), 6);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // The rates file from the README: 1 USD = 0.92 EUR = 0.79 GBP
+    const RATES_FIXTURE: &str = r#"{ "base": "USD", "rates": { "EUR": 0.92, "GBP": 0.79 } }"#;
+
+    fn rates_fixture() -> StaticRateProvider {
+        serde_json::from_str(RATES_FIXTURE).unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn static_rates_convert_directly_and_across_the_base() {
+        let rates = rates_fixture();
+        assert_eq!(rates.rate("USD", "USD"), Some(1.0));
+        assert_close(rates.rate("USD", "EUR").unwrap(), 0.92);
+        assert_close(rates.rate("eur", "usd").unwrap(), 1.0 / 0.92);
+        assert_close(rates.rate("EUR", "GBP").unwrap(), 0.79 / 0.92);
+        assert_eq!(rates.rate("USD", "JPY"), None);
+    }
+
+    #[test]
+    fn static_rates_load_from_a_file() {
+        let dir = scratch_dir("rates_file");
+        let path = dir.join("rates.json");
+        fs::write(&path, RATES_FIXTURE).unwrap();
+
+        let rates = StaticRateProvider::from_file(&path.to_string_lossy()).unwrap();
+        assert_eq!(rates.base, "USD");
+        assert_eq!(rates.per_base("GBP"), Some(0.79));
+        assert!(StaticRateProvider::from_file(&dir.join("none.json").to_string_lossy()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn convert_total_sums_in_the_target_currency() {
+        let amounts = [(100.0, "USD"), (92.0, "EUR"), (79.0, "GBP")];
+        let (total, missing) = convert_total(amounts, "USD", &rates_fixture(), Rounding::HalfUp);
+
+        assert_close(total, 300.0);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn convert_total_leaves_out_unknown_currencies() {
+        let amounts = [(10.0, "USD"), (500.0, "JPY"), (20.0, "CHF"), (700.0, "JPY")];
+        let (total, missing) = convert_total(amounts, "EUR", &rates_fixture(), Rounding::HalfUp);
+
+        assert_close(total, 9.2);
+        assert_eq!(missing, ["JPY", "CHF"]);
+    }
+
+    #[test]
+    fn rates_set_in_the_app_are_saved_and_loaded_back() {
+        let dir = scratch_dir("rates_companion");
+        let path = dir.join("expenses.rates.json");
+
+        save_rates(
+            &MemoryStorage::default(),
+            &StaticRateProvider::new(BASE_CURRENCY),
+            &path,
+        )
+        .unwrap();
+        assert!(!path.exists());
+
+        save_rates(&MemoryStorage::default(), &rates_fixture(), &path).unwrap();
+        let loaded = load_rates(&MemoryStorage::default(), &path).unwrap();
+        assert_eq!(loaded.base, "USD");
+        assert_eq!(loaded.rates, rates_fixture().rates);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trashed_expense_can_be_restored_and_undone() {
+        let mut tracker = tracker();
+        tracker.config.soft_delete = true;
+        tracker.delete_by_index(0).unwrap();
+        tracker.unsaved_changes = false;
+
+        tracker.restore_from_trash(0);
+        assert_eq!(categories(&tracker.expenses), ["Rent", "Travel", "Food"]);
+        assert_eq!(tracker.expenses[2].id, 1);
+        assert!(tracker.trash.is_empty());
+        assert!(tracker.unsaved_changes);
+
+        assert_eq!(tracker.undo_last(), Some("restore"));
+        assert_eq!(categories(&tracker.trash), ["Food"]);
+    }
+
+    #[test]
+    fn emptying_the_trash_removes_everything_in_it() {
+        let mut tracker = tracker();
+        tracker.config.soft_delete = true;
+        tracker.delete_indices(&[0, 1]).unwrap();
+        tracker.unsaved_changes = false;
+
+        tracker.empty_trash();
+        assert!(tracker.trash.is_empty());
+        assert_eq!(categories(&tracker.expenses), ["Travel"]);
+        assert!(tracker.unsaved_changes);
+    }
+
+    #[test]
+    fn trash_is_saved_next_to_the_data_file() {
+        let dir = scratch_dir("trash");
+        let path = companion_path(&dir.join("expenses.json").to_string_lossy(), "trash");
+        assert!(path.ends_with("expenses.trash.json"));
+
+        save_trash(&MemoryStorage::default(), &[], &path).unwrap();
+        assert!(!path.exists());
+        save_trash(&MemoryStorage::default(), &varied_expenses(), &path).unwrap();
+        assert_eq!(
+            as_json(&load_trash(&MemoryStorage::default(), &path).unwrap()),
+            as_json(&varied_expenses())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv_export_writes_expenses_csv_with_the_header_row() {
+        let dir = scratch_dir("csv_file");
+        let path = dir.join(CSV_FILE);
+        let expenses = varied_expenses();
+        let refs: Vec<&Expense> = expenses.iter().take(2).collect();
+        export_to_csv(
+            &refs,
+            &path.to_string_lossy(),
+            &CsvColumn::ALL,
+            false,
+            Rounding::HalfUp,
+        )
+        .unwrap();
+
+        assert!(dir.join("expenses.csv").is_file());
+        let contents = fs::read_to_string(&path).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Id,Date,Category,Amount,Currency,Description,Tags,PaymentMethod,Kind,Note")
+        );
+        assert_eq!(lines.count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // The data file main would use for these arguments: --file, else EXPENSE_TRACKER_FILE,
+    // else the profile's file
+    fn data_path_for(args: &[&str]) -> String {
+        let cli =
+            Cli::try_parse_from(std::iter::once("expense_tracker").chain(args.iter().copied()))
+                .unwrap();
+        cli.file.unwrap_or(profile_paths(&cli.profile).0)
+    }
+
+    #[test]
+    fn data_path_falls_back_to_the_env_var_then_the_profile_file() {
+        // The only test touching these variables, so changing them can't race another test.
+        std::env::remove_var("EXPENSE_TRACKER_PROFILE");
+        std::env::remove_var("EXPENSE_TRACKER_FILE");
+        assert_eq!(data_path_for(&[]), "expenses.json");
+        assert_eq!(data_path_for(&["--profile", "work"]), "expenses_work.json");
+
+        std::env::set_var("EXPENSE_TRACKER_FILE", "/data/mine.json");
+        assert_eq!(data_path_for(&[]), "/data/mine.json");
+        assert_eq!(data_path_for(&["--file", "other.json"]), "other.json");
+        std::env::remove_var("EXPENSE_TRACKER_FILE");
+    }
+
+    #[test]
+    fn saving_creates_the_missing_folders_of_the_data_path() {
+        let dir = scratch_dir("nested_data_path");
+        let path = dir.join("a").join("b").join("expenses.json");
+        let json = JsonStorage {
+            path: path.to_string_lossy().into_owned(),
+            backed_up: Cell::new(false),
+        };
+
+        json.save(&varied_expenses()).unwrap();
+        assert!(path.is_file());
+        assert_eq!(json.load().unwrap().len(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_amount_accepts_only_positive_amounts() {
+        assert_eq!(validate_amount(0.01), Ok(0.01));
+        assert_eq!(validate_amount(2500.0), Ok(2500.0));
+        assert!(validate_amount(0.0).is_err());
+        assert!(validate_amount(-0.0).is_err());
+        assert_eq!(
+            validate_amount(-0.01),
+            Err("Amount must be greater than zero (got -0.01).".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_expenses_drops_non_numeric_amounts_without_failing() {
+        let data = r#"[
+            { "amount": 12.5, "category": "Food", "timestamp": "2024-05-01T09:30:00Z" },
+            { "amount": "NaN", "category": "Food", "timestamp": "2024-05-02T09:30:00Z" },
+            { "amount": null, "category": "Food", "timestamp": "2024-05-03T09:30:00Z" },
+            { "amount": 3.0, "category": "Bus", "timestamp": "2024-05-04T09:30:00Z" }
+        ]"#;
+        let (expenses, warnings) = parse_expenses(data).unwrap();
+
+        assert_eq!(categories(&expenses), ["Food", "Bus"]);
+        assert_eq!(warnings.len(), 2);
+        assert!(
+            warnings[0].starts_with("Skipping entry #2"),
+            "{}",
+            warnings[0]
+        );
+        assert!(parse_expenses("{}").is_err());
+    }
+
+    #[test]
+    fn non_finite_amounts_are_rejected_and_sort_safely() {
+        for amount in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(
+                validate_amount(amount),
+                Err("Amount must be a valid number.".to_string())
+            );
+        }
+
+        let mut expenses = vec![expense("A", 5.0), expense("B", f64::NAN), expense("C", 1.0)];
+        apply_sort(&mut expenses, SortOrder::AmountAsc);
+        assert_eq!(categories(&expenses), ["C", "A", "B"]);
+    }
+
+    #[test]
+    fn parse_expense_timestamp_defaults_to_now_when_blank() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 8, 45, 0).unwrap();
+        assert_eq!(parse_expense_timestamp("", "%Y-%m-%d", now), Ok(now));
+        assert_eq!(parse_expense_timestamp("   ", "%Y-%m-%d", now), Ok(now));
+    }
+
+    #[test]
+    fn parse_expense_timestamp_stamps_the_date_at_noon_utc() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 8, 45, 0).unwrap();
+        assert_eq!(
+            parse_expense_timestamp("03/05/2024", "%d/%m/%Y", now),
+            Ok(Utc.with_ymd_and_hms(2024, 5, 3, 12, 0, 0).unwrap())
+        );
+        assert!(parse_expense_timestamp("2024-05-03", "%d/%m/%Y", now).is_err());
+    }
+
+    #[test]
+    fn parse_expense_timestamp_rejects_years_before_the_minimum() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 8, 45, 0).unwrap();
+        assert_eq!(
+            parse_expense_timestamp("1999-12-31", "%Y-%m-%d", now),
+            Err("'1999-12-31' is before 2000. Please check the year.".to_string())
+        );
+        assert!(parse_expense_timestamp("2000-01-01", "%Y-%m-%d", now).is_ok());
+    }
+
+    #[test]
+    fn parse_expense_timestamp_leaves_future_dates_to_confirm() {
+        let now = Utc::now();
+        let next_year = format!("{}-01-15", now.year() + 1);
+        let timestamp = parse_expense_timestamp(&next_year, "%Y-%m-%d", now).unwrap();
+
+        assert_eq!(timestamp.year(), now.year() + 1);
+        assert!(!is_plausible_date(timestamp));
+        assert!(is_plausible_date(
+            now + chrono::Duration::days(FUTURE_WARNING_DAYS)
+        ));
+    }
+
+    #[test]
+    fn parse_date_range_fills_in_open_ends() {
+        let today = date(2024, 5, 31);
+        assert_eq!(
+            parse_date_range("2024-05-01", "2024-05-15", "%Y-%m-%d", today),
+            Ok((Some(date(2024, 5, 1)), date(2024, 5, 15)))
+        );
+        assert_eq!(
+            parse_date_range("", "", "%Y-%m-%d", today),
+            Ok((None, today))
+        );
+        assert_eq!(
+            parse_date_range("2024-05-20", "2024-05-10", "%Y-%m-%d", today),
+            Err("End date 2024-05-10 is before start date 2024-05-20.".to_string())
+        );
+        assert!(parse_date_range("May 1", "", "%Y-%m-%d", today).is_err());
+    }
+
+    #[test]
+    fn select_by_date_range_includes_both_ends() {
+        let expenses = unsorted();
+        let found = select_by_date_range(&expenses, Some(date(2024, 5, 1)), date(2024, 5, 10));
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].category, "Food");
+        assert_eq!(found[1].category, "rent");
+
+        assert_eq!(
+            select_by_date_range(&expenses, None, date(2024, 5, 31)).len(),
+            3
+        );
+        assert!(
+            select_by_date_range(&expenses, Some(date(2024, 5, 21)), date(2024, 5, 31)).is_empty()
+        );
+    }
+
+    #[test]
+    fn budget_status_warns_from_the_threshold() {
+        assert_eq!(budget_status(79.99, 100.0, 0.8), BudgetStatus::Under);
+        assert_eq!(budget_status(80.0, 100.0, 0.8), BudgetStatus::Near);
+        assert_eq!(budget_status(100.0, 100.0, 0.8), BudgetStatus::Near);
+        assert_eq!(budget_status(100.01, 100.0, 0.8), BudgetStatus::Over);
+        assert_eq!(budget_status(50.0, 100.0, 0.5), BudgetStatus::Near);
+        // A threshold of 100% only warns once the limit is passed.
+        assert_eq!(budget_status(99.0, 100.0, 1.0), BudgetStatus::Under);
+    }
+
+    #[test]
+    fn spent_in_only_counts_the_given_month() {
+        let mut tracker = ExpenseTracker::new();
+        let mut april = expense("Food", 70.0);
+        april.timestamp = Utc.with_ymd_and_hms(2024, 4, 30, 23, 59, 0).unwrap();
+        let mut may = expense("Food", 20.0);
+        may.timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let mut refund = expense("Food", 15.0);
+        refund.kind = EntryKind::Income;
+        tracker.add(april);
+        tracker.add(may);
+        tracker.add(expense("Food", 5.0));
+        tracker.add(refund);
+        tracker.add(expense("Rent", 500.0));
+
+        assert_eq!(tracker.spent_in("Food", 2024, 4), 70.0);
+        assert_eq!(tracker.spent_in("Food", 2024, 5), 25.0);
+        assert_eq!(tracker.spent_in("Food", 2024, 6), 0.0);
+        assert_eq!(tracker.spent_overall(2024, 5), 525.0);
+    }
+
+    #[test]
+    fn spent_in_rounds_each_amount_like_the_summaries() {
+        let mut tracker = ExpenseTracker::new();
+        tracker.add(expense("Food", 0.005));
+        tracker.add(expense("Food", 0.005));
+
+        assert_eq!(tracker.spent_in("Food", 2024, 5), 0.02);
+        tracker.config.rounding = Rounding::HalfEven;
+        assert_eq!(tracker.spent_in("Food", 2024, 5), 0.0);
+    }
+
+    #[test]
+    fn list_position_maps_list_numbers_to_positions() {
+        assert_eq!(list_position(0, 3), None);
+        assert_eq!(list_position(1, 3), Some(0));
+        assert_eq!(list_position(3, 3), Some(2));
+        assert_eq!(list_position(4, 3), None);
+        assert_eq!(list_position(1, 0), None);
+    }
+
+    #[test]
+    fn parse_delete_list_rejects_zero_and_out_of_range_numbers() {
+        assert_eq!(parse_delete_list("2, 5,7,,5", 7), Ok(vec![6, 4, 1]));
+        assert_eq!(parse_delete_list("1", 1), Ok(vec![0]));
+        assert_eq!(
+            parse_delete_list("0,2,8,x", 7),
+            Err(vec!["0".to_string(), "8".to_string(), "x".to_string()])
+        );
+        assert_eq!(
+            parse_delete_list(" , ", 7),
+            Err(vec!["(none entered)".to_string()])
+        );
+    }
+
+    #[test]
+    fn select_by_categories_groups_in_the_order_entered() {
+        let mut expenses = unsorted();
+        expenses.push(expense("FOOD", 4.0));
+        let wanted = parse_comma_list(" rent, food ,, Rent ");
+        assert_eq!(wanted, ["rent", "food"]);
+
+        let found = select_by_categories(&expenses, &wanted);
+        let found: Vec<(&str, f64)> = found
+            .iter()
+            .map(|e| (e.category.as_str(), e.amount))
+            .collect();
+        assert_eq!(found, [("rent", 500.0), ("Food", 10.0), ("FOOD", 4.0)]);
+    }
+
+    #[test]
+    fn select_by_amount_uses_open_or_inclusive_bounds() {
+        let mut expenses = unsorted();
+        let mut salary = expense("Salary", 42.5);
+        salary.kind = EntryKind::Income;
+        expenses.push(salary);
+
+        assert_eq!(parse_amount_bound(" "), Ok(None));
+        assert_eq!(parse_amount_bound("10"), Ok(Some(10.0)));
+        assert!(parse_amount_bound("ten").is_err());
+        assert!(parse_amount_bound("inf").is_err());
+
+        let amounts = |min, max| -> Vec<f64> {
+            select_by_amount(&expenses, min, max)
+                .iter()
+                .map(|e| e.amount)
+                .collect()
+        };
+        assert_eq!(amounts(Some(10.0), Some(42.5)), [10.0, 42.5]);
+        assert_eq!(amounts(None, Some(10.0)), [10.0]);
+        assert_eq!(amounts(Some(100.0), None), [500.0]);
+        assert_eq!(amounts(None, None).len(), 3);
+    }
+
+    #[test]
+    fn export_writes_only_the_filtered_subset() {
+        let expenses = unsorted();
+        let subset: Vec<Expense> = select_by_amount(&expenses, Some(20.0), None)
+            .into_iter()
+            .cloned()
+            .collect();
+        let rows = exported_csv(
+            "subset",
+            &subset,
+            &[CsvColumn::Category, CsvColumn::Amount],
+            false,
+        );
+
+        assert_eq!(
+            rows,
+            [["Category", "Amount"], ["rent", "500"], ["Travel", "42.5"]]
+        );
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_and_cleans_up() {
+        let dir = scratch_dir("atomic");
+        let path = dir.join("expenses.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new contents").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new contents");
+        assert!(!dir.join("expenses.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_leaves_the_original_alone_when_it_fails() {
+        let dir = scratch_dir("atomic_fail");
+        let path = dir.join("expenses.json");
+        fs::write(&path, "old").unwrap();
+        // A folder where the temporary file should go makes creating it fail.
+        fs::create_dir(dir.join("expenses.json.tmp")).unwrap();
+
+        assert!(write_atomic(&path, b"new contents").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saving_over_a_data_file_keeps_a_backup() {
+        let dir = scratch_dir("backup");
+        let path = dir.join("expenses.json");
+        let mut expenses = varied_expenses();
+        save_json_expenses(&expenses, &path.to_string_lossy(), true).unwrap();
+        assert!(list_backups(&path).unwrap().is_empty());
+
+        expenses.pop();
+        save_json_expenses(&expenses, &path.to_string_lossy(), true).unwrap();
+        let backups = list_backups(&path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            load_json_expenses(&backups[0].to_string_lossy())
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(
+            load_json_expenses(&path.to_string_lossy()).unwrap().len(),
+            2
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_category_rejects_blank_input() {
+        assert_eq!(validate_category(""), Err("The category can't be empty."));
+        assert_eq!(
+            validate_category(" \t "),
+            Err("The category can't be empty.")
+        );
+        assert_eq!(validate_category(" Food "), Ok(()));
+    }
+
+    #[test]
+    fn normalize_category_gives_one_spelling() {
+        assert_eq!(normalize_category("food"), "Food");
+        assert_eq!(normalize_category(" FOOD "), "Food");
+        assert_eq!(normalize_category("eating  out"), "Eating Out");
+        assert_eq!(normalize_category("élan vital"), "Élan Vital");
+    }
+
+    #[test]
+    fn saving_under_a_file_returns_an_error() {
+        let dir = scratch_dir("save_error");
+        let blocker = dir.join("not_a_folder");
+        fs::write(&blocker, "").unwrap();
+        let path = blocker.join("expenses.json").to_string_lossy().into_owned();
+
+        assert!(save_json_expenses(&varied_expenses(), &path, true).is_err());
+        let refs: Vec<&Expense> = Vec::new();
+        assert!(export_to_csv(&refs, &path, &CsvColumn::ALL, false, Rounding::HalfUp).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_data_file_is_an_error_not_an_empty_list() {
+        let dir = scratch_dir("load_error");
+        // Reading a folder fails with something other than NotFound.
+        assert!(load_json_expenses(&dir.to_string_lossy()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_data_file_is_moved_aside_and_loads_empty() {
+        let dir = scratch_dir("corrupt");
+        let path = dir.join("expenses.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        assert!(load_json_expenses(&path.to_string_lossy())
+            .unwrap()
+            .is_empty());
+        assert!(!path.exists());
+        let moved: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(moved.len(), 1);
+        assert!(
+            moved[0].starts_with("expenses.json.corrupt-"),
+            "{}",
+            moved[0]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn round_cents_breaks_ties_by_the_rounding_mode() {
+        assert_eq!(Rounding::HalfUp.round_cents(0.005), 0.01);
+        assert_eq!(Rounding::HalfUp.round_cents(0.015), 0.02);
+        assert_eq!(Rounding::HalfEven.round_cents(0.005), 0.0);
+        assert_eq!(Rounding::HalfEven.round_cents(0.015), 0.02);
+        assert_eq!(Rounding::HalfEven.round_cents(0.025), 0.02);
+        // 1.005 is stored as 1.00499999..., but still counts as a tie.
+        assert_eq!(Rounding::HalfUp.round_cents(1.005), 1.01);
+        assert_eq!(Rounding::HalfUp.round_cents(-0.005), -0.01);
+        assert_eq!(Rounding::HalfUp.round_cents(12.344), 12.34);
+    }
+
+    #[test]
+    fn totals_add_up_the_rounded_amounts() {
+        let expenses = [
+            expense("Food", 0.005),
+            expense("Food", 0.005),
+            expense("Bus", 0.015),
+        ];
+        let refs: Vec<&Expense> = expenses.iter().collect();
+
+        assert_eq!(format_totals(&refs, Rounding::HalfUp), "$0.04");
+        assert_eq!(format_totals(&refs, Rounding::HalfEven), "$0.02");
+        let totals = category_totals(&expenses, Rounding::HalfEven);
+        assert_eq!(totals.get("Food"), Some(&0.0));
+        assert_eq!(totals.get("Bus"), Some(&0.02));
+    }
+
+    #[test]
+    fn over_budget_list_agrees_with_spent_in() {
+        let mut tracker = ExpenseTracker::new();
+        tracker.config.rounding = Rounding::HalfEven;
+        tracker.add(expense("Food", 10.005));
+        tracker.add(expense("Food", 10.005));
+        tracker
+            .set_budget("Food", BudgetKind::Limit, 20.01)
+            .unwrap();
+
+        // Unrounded the two add up to 20.01 exactly; rounded half-even they are 20.00.
+        assert_eq!(tracker.spent_in("Food", 2024, 5), 20.0);
+        assert!(over_budget_categories(
+            &tracker.expenses,
+            &tracker.budgets,
+            2024,
+            5,
+            Rounding::HalfEven
+        )
+        .is_empty());
+
+        tracker
+            .set_budget("Food", BudgetKind::Limit, 19.99)
+            .unwrap();
+        let over = over_budget_categories(
+            &tracker.expenses,
+            &tracker.budgets,
+            2024,
+            5,
+            Rounding::HalfEven,
+        );
+        assert_eq!(over, [("Food".to_string(), 20.0, 19.99)]);
+    }
+
+    // The fields a CSV row carries, for comparing expenses across an export and import
+    type CsvFields = (
+        String,
+        f64,
+        String,
+        String,
+        Vec<String>,
+        String,
+        i64,
+        EntryKind,
+        String,
+    );
+
+    fn csv_fields(e: &Expense) -> CsvFields {
+        (
+            e.category.clone(),
+            e.amount,
+            e.currency.clone(),
+            e.description.clone(),
+            e.tags.clone(),
+            e.payment_method.clone(),
+            e.timestamp.timestamp(),
+            e.kind,
+            e.note.clone(),
+        )
+    }
+
+    #[test]
+    fn csv_export_imports_back_with_every_field() {
+        let dir = scratch_dir("csv_round_trip");
+        let path = dir.join(CSV_FILE).to_string_lossy().into_owned();
+        let mut expenses = varied_expenses();
+        expenses[0].description = "Coffee, \"large\"".to_string();
+        expenses[0].note = "Paid for the team;\nclaim it back".to_string();
+        expenses[1].timestamp = Utc.with_ymd_and_hms(2024, 5, 11, 19, 45, 30).unwrap();
+        assert!(expenses[2].is_income());
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        export_to_csv(&refs, &path, &CsvColumn::ALL, false, Rounding::HalfUp).unwrap();
+
+        let mut tracker = ExpenseTracker::new();
+        let (imported, skipped) = import_from_csv(&path, &mut tracker).unwrap();
+        assert_eq!(imported, 3);
+        assert!(skipped.is_empty(), "{:?}", skipped);
+        let fields: Vec<_> = tracker.expenses.iter().map(csv_fields).collect();
+        let expected: Vec<_> = expenses.iter().map(csv_fields).collect();
+        assert_eq!(fields, expected);
+        assert!(tracker.unsaved_changes);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv_columns_come_in_a_stable_order() {
+        let rows = exported_csv("csv_order", &varied_expenses()[..1], &CsvColumn::ALL, false);
+        assert_eq!(
+            rows[1],
+            [
+                "1",
+                "2024-05-10T12:00:00+00:00",
+                "Food",
+                "3.5",
+                "USD",
+                "Coffee",
+                "work;reimbursable",
+                "Unknown",
+                "expense",
+                ""
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_csv_timestamp_reads_old_and_new_exports() {
+        let expected = Utc.with_ymd_and_hms(2024, 5, 1, 9, 30, 0).unwrap();
+        assert_eq!(
+            parse_csv_timestamp("2024-05-01T09:30:00Z", "%d/%m/%Y"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_csv_timestamp("2024-05-01T11:30:00+02:00", "%d/%m/%Y"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_csv_timestamp("2024-05-01 09:30:00 UTC", "%d/%m/%Y"),
+            Some(expected)
+        );
+        let noon = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        assert_eq!(parse_csv_timestamp("01/05/2024", "%d/%m/%Y"), Some(noon));
+        assert_eq!(parse_csv_timestamp("2024-05-01", "%d/%m/%Y"), Some(noon));
+        assert_eq!(parse_csv_timestamp("yesterday", "%d/%m/%Y"), None);
+    }
+
+    #[test]
+    fn days_in_month_handles_leap_years_and_december() {
+        assert_eq!(days_in_month(2024, 1), 31);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2024, 4), 30);
+        assert_eq!(days_in_month(2024, 12), 31);
+    }
+
+    #[test]
+    fn shift_to_month_keeps_the_day_and_time_when_it_fits() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 3, 31, 18, 5, 9).unwrap();
+        assert_eq!(
+            shift_to_month(timestamp, 2024, 2),
+            Utc.with_ymd_and_hms(2024, 2, 29, 18, 5, 9).unwrap()
+        );
+        assert_eq!(
+            shift_to_month(timestamp, 2024, 5),
+            Utc.with_ymd_and_hms(2024, 5, 31, 18, 5, 9).unwrap()
+        );
+        assert_eq!(
+            shift_to_month(timestamp, 2025, 1),
+            Utc.with_ymd_and_hms(2025, 1, 31, 18, 5, 9).unwrap()
+        );
+    }
+
+    #[test]
+    fn clone_candidates_copies_last_months_entries_once() {
+        let in_month = |category: &str, amount: f64, year, month, day| {
+            let mut e = expense(category, amount);
+            e.timestamp = Utc.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap();
+            e
+        };
+        let expenses = vec![
+            in_month("Rent", 900.0, 2024, 1, 31),
+            in_month("Gym", 30.0, 2024, 1, 5),
+            in_month("Food", 12.0, 2024, 1, 8),
+            in_month("Gym", 30.0, 2024, 2, 5),
+            in_month("Rent", 850.0, 2023, 12, 31),
+        ];
+        let wanted = ["Rent".to_string(), "Gym".to_string()];
+
+        let copies = clone_candidates(&expenses, &wanted, 2024, 2);
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].category, "Rent");
+        assert_eq!(copies[0].amount, 900.0);
+        assert_eq!(
+            copies[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 2, 29, 9, 0, 0).unwrap()
+        );
+
+        // January reaches back across the year to December.
+        let copies = clone_candidates(&expenses, &wanted, 2024, 1);
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].amount, 850.0);
+    }
+
+    #[test]
+    fn parse_csv_columns_keeps_the_order_given() {
+        assert_eq!(
+            parse_csv_columns("date, AMOUNT,category, Date"),
+            Ok(vec![
+                CsvColumn::Date,
+                CsvColumn::Amount,
+                CsvColumn::Category
+            ])
+        );
+        assert_eq!(
+            parse_csv_columns("Payment Method,tags"),
+            Ok(vec![CsvColumn::PaymentMethod, CsvColumn::Tags])
+        );
+    }
+
+    #[test]
+    fn parse_csv_columns_rejects_unknown_or_missing_names() {
+        let unknown = parse_csv_columns("Date,Price").unwrap_err();
+        assert!(
+            unknown.starts_with("Unknown column 'Price'."),
+            "{}",
+            unknown
+        );
+        let empty = parse_csv_columns(" , ").unwrap_err();
+        assert!(empty.starts_with("Pick at least one column"), "{}", empty);
+    }
+
+    #[test]
+    fn csv_export_writes_only_the_chosen_columns() {
+        let columns = parse_csv_columns("Amount,Id").unwrap();
+        let rows = exported_csv("csv_columns", &varied_expenses(), &columns, false);
+        assert_eq!(rows[0], ["Amount", "Id"]);
+        assert_eq!(rows[2], ["84.2", "2"]);
+        assert!(rows.iter().all(|row| row.len() == 2));
+    }
+
+    #[test]
+    fn corrupt_companion_file_is_moved_aside_instead_of_overwritten() {
+        let dir = scratch_dir("corrupt_companion");
+        let data_path = dir.join("expenses.json").to_string_lossy().into_owned();
+        let budgets_path = companion_path(&data_path, "budgets");
+        fs::write(&budgets_path, "{ \"Food\": ").unwrap();
+
+        let mut tracker = ExpenseTracker::new();
+        load_companions(&mut tracker, &data_path).unwrap();
+        assert!(tracker.budgets.is_empty());
+        assert!(!budgets_path.exists());
+        let moved: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(moved.len(), 1);
+        assert!(
+            moved[0].starts_with("expenses.budgets.json.corrupt-"),
+            "{}",
+            moved[0]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(&moved[0])).unwrap(),
+            "{ \"Food\": "
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_companion_file_is_an_error() {
+        let dir = scratch_dir("unreadable_companion");
+        let data_path = dir.join("expenses.json").to_string_lossy().into_owned();
+        fs::create_dir(companion_path(&data_path, "trash")).unwrap();
+
+        let mut tracker = ExpenseTracker::new();
+        assert!(load_companions(&mut tracker, &data_path).is_err());
+        assert!(load_trash(
+            &MemoryStorage::default(),
+            &companion_path(&data_path, "trash")
+        )
+        .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn companions_save_and_load_back() {
+        let dir = scratch_dir("companions");
+        let data_path = dir.join("expenses.json").to_string_lossy().into_owned();
+        let mut tracker = tracker();
+        tracker
+            .set_budget("Food", BudgetKind::Limit, 200.0)
+            .unwrap();
+        tracker.monthly_budget = Some(1500.0);
+        tracker.trash = varied_expenses();
+        tracker.rates = rates_fixture();
+        save_companions(&tracker, tracker.storage.as_ref(), &data_path).unwrap();
+
+        let mut loaded = ExpenseTracker::new();
+        load_companions(&mut loaded, &data_path).unwrap();
+        assert_eq!(loaded.budgets, tracker.budgets);
+        assert_eq!(loaded.monthly_budget, Some(1500.0));
+        assert_eq!(as_json(&loaded.trash), as_json(&tracker.trash));
+        assert_eq!(loaded.rates.rates, tracker.rates.rates);
+        assert!(loaded.recurring.is_empty());
+        assert!(!companion_path(&data_path, "recurring").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_companion_save_is_returned() {
+        let dir = scratch_dir("companion_save_error");
+        let blocker = dir.join("not_a_folder");
+        fs::write(&blocker, "").unwrap();
+        let data_path = blocker.join("expenses.json").to_string_lossy().into_owned();
+        let mut tracker = tracker();
+        tracker
+            .set_budget("Food", BudgetKind::Limit, 200.0)
+            .unwrap();
+
+        assert!(save_companions(&tracker, tracker.storage.as_ref(), &data_path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn record_expense_returns_the_confirmation_and_budget_lines() {
+        let mut tracker = tracker();
+        tracker.budgets.insert("Food".to_string(), 5.0);
+        let mut lunch = expense("Food", 12.5);
+        lunch.timestamp = Utc::now();
+
+        let messages = record_expense(&mut tracker, lunch);
+
+        assert_eq!(tracker.expenses.len(), 4);
+        assert_eq!(messages[0], "✅ Expense added: Food - $12.50");
+        assert!(messages[1..]
+            .iter()
+            .any(|m| m.contains("exceeded your budget")));
+
+        let mut salary = expense("Salary", 100.0);
+        salary.kind = EntryKind::Income;
+        assert_eq!(record_expense(&mut tracker, salary).len(), 1);
+    }
+
+    #[test]
+    fn csv_totals_keep_the_amount_without_a_category_column() {
+        let columns = parse_csv_columns("Amount,Date").unwrap();
+        let rows = exported_csv("csv_totals_no_category", &varied_expenses(), &columns, true);
+        assert_eq!(rows[4], ["", ""]);
+        assert!(rows.contains(&vec!["84.20".to_string(), "TOTAL: Dining".to_string()]));
+        assert_eq!(rows.last().unwrap(), &["3.50", "TOTAL"]);
+    }
+
+    #[test]
+    fn csv_totals_need_a_column_for_their_labels() {
+        let dir = scratch_dir("csv_totals_no_label");
+        let path = dir.join(CSV_FILE);
+        let expenses = varied_expenses();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+        let columns = [CsvColumn::Amount, CsvColumn::Currency];
+        let path_str = path.to_string_lossy();
+        assert!(export_to_csv(&refs, &path_str, &columns, true, Rounding::HalfUp).is_err());
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_storage_encrypts_the_companion_files_too() {
+        let dir = scratch_dir("encrypted_companions");
+        let data_path = dir.join("expenses.json").to_string_lossy().into_owned();
+        let storage = EncryptedStorage {
+            path: data_path.clone(),
+            passphrase: "correct horse".to_string(),
+            backed_up: Cell::new(false),
+        };
+        let trash_path = companion_path(&data_path, "trash");
+        save_trash(&storage, &varied_expenses(), &trash_path).unwrap();
+
+        let contents = fs::read(&trash_path).unwrap();
+        assert!(contents.starts_with(ENCRYPTED_MAGIC));
+        assert!(!String::from_utf8_lossy(&contents).contains("Coffee"));
+        assert_eq!(
+            as_json(&load_trash(&storage, &trash_path).unwrap()),
+            as_json(&varied_expenses())
+        );
+
+        let wrong = EncryptedStorage {
+            path: data_path,
+            passphrase: "wrong".to_string(),
+            backed_up: Cell::new(false),
+        };
+        assert!(load_trash(&wrong, &trash_path).is_err());
+        assert!(trash_path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stats_and_converted_totals_add_up_rounded_cents() {
+        let expenses = [expense("Food", 0.125), expense("Food", 0.125)];
+        let refs: Vec<&Expense> = expenses.iter().collect();
+
+        // Unrounded the two make 0.25; rounded half-even each is 0.12.
+        let stats = expense_stats(&refs, Rounding::HalfEven).unwrap();
+        assert_eq!(stats.total, 0.24);
+        assert_eq!(stats.average, 0.12);
+        assert_eq!(stats.median, 0.12);
+
+        let amounts = expenses.iter().map(|e| (e.amount, e.currency.as_str()));
+        let (total, _) = convert_total(amounts, "USD", &rates_fixture(), Rounding::HalfEven);
+        assert_eq!(total, 0.24);
+    }
+
+    #[test]
+    fn imports_normalize_categories_and_skip_blank_ones() {
+        let dir = scratch_dir("import_categories");
+        let expenses_csv = dir.join("bank.csv");
+        fs::write(
+            &expenses_csv,
+            "Category,Amount,Date\n eating  OUT ,12.5,2024-05-01\n  ,3,2024-05-02\n",
+        )
+        .unwrap();
+        let mut tracker = ExpenseTracker::new();
+        let (imported, skipped) =
+            import_from_csv(&expenses_csv.to_string_lossy(), &mut tracker).unwrap();
+        assert_eq!(imported, 1);
+        assert_eq!(skipped, ["Row 3: The category can't be empty."]);
+        assert_eq!(tracker.expenses[0].category, "Eating Out");
+
+        let budgets_csv = dir.join("budgets.csv");
+        fs::write(&budgets_csv, "Category,Budget\nfood,200\n,50\nRENT,-100\n").unwrap();
+        tracker
+            .set_budget("Food", BudgetKind::Limit, 150.0)
+            .unwrap();
+        tracker.unsaved_changes = false;
+        let result = import_budgets(&mut tracker, &budgets_csv.to_string_lossy()).unwrap();
+        assert_eq!((result.added, result.updated, result.skipped), (1, 1, 1));
+        assert_eq!(tracker.budgets["Food"], 200.0);
+        assert_eq!(tracker.budgets["Rent"], -100.0);
+        assert_eq!(tracker.budgets.len(), 2);
+        assert!(tracker.unsaved_changes);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn set_budget_refuses_amounts_that_are_not_finite() {
+        let mut tracker = tracker();
+        assert!(tracker
+            .set_budget("Food", BudgetKind::Limit, f64::NAN)
+            .is_err());
+        assert!(tracker
+            .set_budget("Food", BudgetKind::Savings, f64::INFINITY)
+            .is_err());
+        assert!(tracker.budgets.is_empty());
+        assert!(!tracker.unsaved_changes);
+
+        let dir = scratch_dir("import_infinite_budget");
+        let path = dir.join("budgets.csv");
+        fs::write(&path, "Category,Budget\nFood,inf\nRent,NaN\nTravel,80\n").unwrap();
+        let result = import_budgets(&mut tracker, &path.to_string_lossy()).unwrap();
+        assert_eq!((result.added, result.skipped), (1, 2));
+        assert_eq!(tracker.budgets.keys().collect::<Vec<_>>(), ["Travel"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tags_and_labels_are_kept_apart() {
+        let json = r#"[{"amount": 12.5, "category": "Food", "timestamp": "2024-05-01T09:30:00Z",
+            "tags": ["work"], "labels": ["vacation"]}]"#;
+        let (expenses, _) = parse_expenses(json).unwrap();
+        assert_eq!(expenses[0].tags, ["work"]);
+        assert_eq!(expenses[0].labels, ["vacation"]);
+
+        assert!(has_tag(&expenses[0], "WORK"));
+        assert!(!has_tag(&expenses[0], "vacation"));
+        assert!(has_label(&expenses[0], "Vacation"));
+        assert!(!has_label(&expenses[0], "work"));
+    }
+
+    #[test]
+    fn add_all_adds_a_batch_under_one_undo_point() {
+        let mut tracker = tracker();
+        assert_eq!(tracker.add_all("import", Vec::new()), 0);
+        assert!(tracker.undo.is_none());
+        assert!(!tracker.unsaved_changes);
+
+        let batch = vec![expense("Food", 1.0), expense("Food", 2.0)];
+        assert_eq!(tracker.add_all("import", batch), 2);
+        let ids: Vec<u64> = tracker.expenses.iter().map(|e| e.id).collect();
+        assert_eq!(ids, [1, 2, 3, 4, 5]);
+        assert!(tracker.unsaved_changes);
+
+        assert_eq!(tracker.undo_last(), Some("import"));
+        assert_eq!(tracker.expenses.len(), 3);
+    }
+
+    #[test]
+    fn due_recurring_bills_are_posted_once_and_undo_together() {
+        let mut tracker = tracker();
+        for (category, day) in [("Rent", 1), ("Gym", 5), ("Phone", 28)] {
+            tracker.recurring.push(RecurringTemplate {
+                category: category.to_string(),
+                amount: 20.0,
+                currency: BASE_CURRENCY.to_string(),
+                day,
+                last_posted: None,
+            });
+        }
+
+        post_due_recurring(&mut tracker, date(2024, 6, 10));
+        assert_eq!(tracker.expenses.len(), 5);
+        assert_eq!(tracker.expenses[3].id, 4);
+        assert_eq!(tracker.expenses[4].category, "Gym");
+        assert_eq!(tracker.recurring[2].last_posted, None);
+
+        post_due_recurring(&mut tracker, date(2024, 6, 11));
+        assert_eq!(tracker.expenses.len(), 5);
+
+        assert_eq!(tracker.undo_last(), Some("recurring posting"));
+        assert_eq!(tracker.expenses.len(), 3);
+    }
+
+    #[test]
+    fn month_report_keeps_currencies_apart() {
+        let dir = scratch_dir("month_report_currencies");
+        let config = Config {
+            reports_dir: dir.to_string_lossy().into_owned(),
+            report_format: ReportFormat::Csv,
+            ..Config::default()
+        };
+        let mut expenses = [expense("Food", 10.0), expense("Food", 4.5)];
+        expenses[1].currency = "EUR".to_string();
+        let refs: Vec<&Expense> = expenses.iter().collect();
+
+        let path = write_month_report(&refs, 2024, 5, &config).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Category,Currency,Total\nFood,EUR,4.50\nTOTAL,EUR,4.50\nFood,USD,10.00\nTOTAL,USD,10.00\n"
+        );
+
+        let config = Config {
+            report_format: ReportFormat::Markdown,
+            ..config
+        };
+        let path = write_month_report(&refs, 2024, 5, &config).unwrap();
+        let markdown = fs::read_to_string(&path).unwrap();
+        assert!(markdown.contains("| Food | €4.50 |"), "{}", markdown);
+        assert!(
+            markdown.contains("| **Total** | **$10.00** |"),
+            "{}",
+            markdown
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn budget_warnings_use_the_given_currency() {
+        let over = budget_warning("Food", 100.0, 120.0, 0.8, "EUR").unwrap();
+        assert!(over.contains("exceeded your budget of €100.00"), "{}", over);
+        let error = build_split(10.0, &[("Me".to_string(), 4.0)], "GBP").unwrap_err();
+        assert_eq!(error, "Shares add up to £4.00 but the expense is £10.00.");
+    }
+
+    #[test]
+    fn json_storage_backs_up_only_on_the_first_save_of_a_session() {
+        let dir = scratch_dir("session-backup");
+        let path = dir.join("expenses.json");
+        let mut expenses = varied_expenses();
+        save_json_expenses(&expenses, &path.to_string_lossy(), false).unwrap();
+
+        let storage = JsonStorage {
+            path: path.to_string_lossy().into_owned(),
+            backed_up: Cell::new(false),
+        };
+        expenses.pop();
+        storage.save(&expenses).unwrap();
+        expenses.pop();
+        storage.save(&expenses).unwrap();
+
+        let backups = list_backups(&path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            load_json_expenses(&backups[0].to_string_lossy())
+                .unwrap()
+                .len(),
+            3
+        );
+        fs::remove_dir_all(&dir).unwrap();
     }
 }